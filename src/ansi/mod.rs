@@ -0,0 +1,536 @@
+mod music;
+pub use self::music::{Articulation, Music, Note};
+use crate::ascii;
+use crate::bytes::read_file_to_bytes;
+use crate::canvas::{Canvas, Cell};
+use crate::sauce::{DataType, FileType, Sauce};
+use crate::Result;
+
+static ESC: u8 = 0x1b;
+static TAB_WIDTH: usize = 8;
+pub static MAX_ROWS: usize = 9999;
+
+pub struct Ansi {
+    pub canvas: Canvas,
+    pub music: Vec<Music>,
+    x: usize,
+    y: usize,
+    saved_position: Option<(usize, usize)>,
+    fg: u8,
+    bg: u8,
+    bold: bool,
+    blink: bool,
+    inverse: bool,
+    font_slots: [Option<u8>; 4],
+    alt_charset: bool,
+    bright_intensity_disabled: bool,
+    bright_background: bool,
+    blink_font: bool,
+    bright_blink_font: bool,
+    music_mode: usize,
+}
+
+struct Sequence {
+    params: Vec<Option<usize>>,
+    private: Option<u8>,
+    intermediate: Option<u8>,
+    terminator: u8,
+}
+
+impl Sequence {
+    fn param(&self, index: usize, default: usize) -> usize {
+        match self.params.get(index) {
+            Some(Some(value)) => *value,
+            _ => default,
+        }
+    }
+}
+
+impl Ansi {
+    pub fn new(width: usize) -> Ansi {
+        Ansi {
+            canvas: Canvas::new(width.max(1), 0),
+            music: Vec::new(),
+            x: 0,
+            y: 0,
+            saved_position: None,
+            fg: 7,
+            bg: 0,
+            bold: false,
+            blink: false,
+            inverse: false,
+            font_slots: [None; 4],
+            alt_charset: false,
+            bright_intensity_disabled: false,
+            bright_background: false,
+            blink_font: false,
+            bright_blink_font: false,
+            music_mode: 0,
+        }
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Ansi {
+        let mut width = 80;
+        let mut ice_colors = false;
+        if let Ok(Some(sauce)) = Sauce::from_bytes(bytes) {
+            if let (Some(DataType::Character), Some(FileType::ANSI))
+            | (Some(DataType::Character), Some(FileType::ANSImation)) =
+                (&sauce.datatype, &sauce.filetype)
+            {
+                if sauce.info_1 > 0 {
+                    width = sauce.info_1;
+                }
+            }
+            ice_colors = sauce.ice_colors;
+        }
+        let mut ansi = Ansi::new(width);
+        ansi.canvas.ice_colors = ice_colors;
+        ansi.interpret(bytes);
+        ansi
+    }
+
+    pub fn from_file(file: &str) -> Result<Ansi> {
+        let bytes = read_file_to_bytes(file)?;
+        Ok(Ansi::from_bytes(&bytes))
+    }
+
+    pub fn cursor(&self) -> (usize, usize) {
+        (self.x, self.y)
+    }
+
+    pub fn interpret(&mut self, bytes: &[u8]) {
+        let mut i = 0;
+        while i < bytes.len() {
+            let byte = bytes[i];
+            i += 1;
+            match byte {
+                _ if byte == ascii::EOF => break,
+                b'\r' => self.x = 0,
+                b'\n' => {
+                    self.x = 0;
+                    self.y += 1;
+                }
+                b'\t' => self.tab(),
+                0x08 => self.x = self.x.saturating_sub(1),
+                _ if byte == ESC && bytes.get(i) == Some(&b'[') => {
+                    if let Some((sequence, length)) = Ansi::parse_sequence(&bytes[i + 1..]) {
+                        i += 1 + length;
+                        if self.music_introducer(&sequence, &bytes[i..]) {
+                            let implied_m = sequence.terminator == b'M';
+                            if let Some((music, length)) = Music::from_bytes(&bytes[i..], implied_m)
+                            {
+                                self.music.push(music);
+                                i += length;
+                                continue;
+                            }
+                        }
+                        self.control_sequence(&sequence);
+                    } else {
+                        break;
+                    }
+                }
+                _ => self.put(byte),
+            }
+        }
+    }
+
+    fn parse_sequence(bytes: &[u8]) -> Option<(Sequence, usize)> {
+        let mut sequence = Sequence {
+            params: Vec::new(),
+            private: None,
+            intermediate: None,
+            terminator: 0,
+        };
+        let mut value: Option<usize> = None;
+        for (i, &byte) in bytes.iter().enumerate() {
+            match byte {
+                b'0'..=b'9' => {
+                    let digit = (byte - b'0') as usize;
+                    value = Some(value.unwrap_or(0).saturating_mul(10).saturating_add(digit));
+                }
+                b';' => sequence.params.push(value.take()),
+                b'<' | b'=' | b'>' | b'?' if i == 0 => sequence.private = Some(byte),
+                b':' | b'<'..=b'?' => {}
+                0x20..=0x2f => sequence.intermediate = Some(byte),
+                0x40..=0x7e => {
+                    if value.is_some() || !sequence.params.is_empty() {
+                        sequence.params.push(value);
+                    }
+                    sequence.terminator = byte;
+                    return Some((sequence, i + 1));
+                }
+                _ => return None,
+            }
+        }
+        None
+    }
+
+    fn control_sequence(&mut self, sequence: &Sequence) {
+        match (sequence.private, sequence.intermediate, sequence.terminator) {
+            (None, None, b'A') => self.y = self.y.saturating_sub(sequence.param(0, 1)),
+            (None, None, b'B') => self.move_down(sequence.param(0, 1)),
+            (None, None, b'C') => {
+                self.x = (self.x.saturating_add(sequence.param(0, 1))).min(self.canvas.width - 1)
+            }
+            (None, None, b'D') => self.x = self.x.saturating_sub(sequence.param(0, 1)),
+            (None, None, b'E') => {
+                self.x = 0;
+                self.move_down(sequence.param(0, 1));
+            }
+            (None, None, b'F') => {
+                self.x = 0;
+                self.y = self.y.saturating_sub(sequence.param(0, 1));
+            }
+            (None, None, b'G') => {
+                self.x = (sequence.param(0, 1).max(1) - 1).min(self.canvas.width - 1)
+            }
+            (None, None, b'H') | (None, None, b'f') => {
+                self.y = (sequence.param(0, 1).max(1) - 1).min(MAX_ROWS - 1);
+                self.x = (sequence.param(1, 1).max(1) - 1).min(self.canvas.width - 1);
+            }
+            (None, None, b'J') => self.erase_in_page(sequence.param(0, 0)),
+            (None, None, b'K') => self.erase_in_line(sequence.param(0, 0)),
+            (None, None, b'L') => {
+                let blank = self.blank();
+                let count = sequence.param(0, 1).min(MAX_ROWS - self.y);
+                self.canvas.insert_rows(self.y, count, blank);
+                self.canvas.remove_rows(MAX_ROWS, count);
+            }
+            (None, None, b'M') => self.canvas.remove_rows(self.y, sequence.param(0, 1)),
+            (None, None, b'@') => self.insert_chars(sequence.param(0, 1)),
+            (None, None, b'P') => self.delete_chars(sequence.param(0, 1)),
+            (None, None, b'X') => {
+                let blank = self.blank();
+                let to_x = self.x.saturating_add(sequence.param(0, 1));
+                self.canvas.fill_row(self.y, self.x, to_x, blank);
+            }
+            (None, None, b'm') => self.select_graphic_rendition(&sequence.params),
+            (None, None, b's') => self.saved_position = Some((self.x, self.y)),
+            (None, None, b'u') => {
+                if let Some((x, y)) = self.saved_position {
+                    self.x = x;
+                    self.y = y;
+                }
+            }
+            (None, Some(b' '), b'D') => {
+                let slot = sequence.param(0, 0);
+                if slot < self.font_slots.len() {
+                    self.font_slots[slot] = Some(sequence.param(1, 0).min(255) as u8);
+                }
+            }
+            (Some(b'='), None, b'M') => self.music_mode = sequence.param(0, 0).min(2),
+            (Some(b'?'), None, b'h') => self.set_modes(&sequence.params, true),
+            (Some(b'?'), None, b'l') => self.set_modes(&sequence.params, false),
+            _ => {}
+        }
+    }
+
+    fn music_introducer(&self, sequence: &Sequence, bytes: &[u8]) -> bool {
+        if sequence.private.is_some() || sequence.intermediate.is_some() {
+            return false;
+        }
+        match sequence.terminator {
+            b'|' => true,
+            b'N' => sequence.params.is_empty() && self.music_mode >= 1,
+            b'M' if sequence.params.is_empty() => {
+                if self.music_mode == 2 {
+                    return true;
+                }
+                match bytes.first().map(|byte| byte.to_ascii_uppercase()) {
+                    Some(b'B') | Some(b'F') | Some(b'L') | Some(b'S') => true,
+                    Some(b'N') => !matches!(bytes.get(1), Some(b'0'..=b'9')),
+                    _ => false,
+                }
+            }
+            _ => false,
+        }
+    }
+
+    fn set_modes(&mut self, params: &[Option<usize>], value: bool) {
+        for param in params.iter().flatten() {
+            match param {
+                31 => self.alt_charset = value,
+                32 => self.bright_intensity_disabled = value,
+                33 => self.bright_background = value,
+                34 => self.blink_font = value,
+                35 => self.bright_blink_font = value,
+                _ => {}
+            }
+        }
+    }
+
+    fn select_graphic_rendition(&mut self, params: &[Option<usize>]) {
+        if params.is_empty() {
+            self.reset_attributes();
+        }
+        for param in params {
+            match param.unwrap_or(0) {
+                0 => self.reset_attributes(),
+                1 => self.bold = true,
+                2 | 22 => self.bold = false,
+                5 | 6 => self.blink = true,
+                25 => self.blink = false,
+                7 => self.inverse = true,
+                27 => self.inverse = false,
+                value @ 30..=37 => self.fg = ansi_to_vga(value - 30),
+                39 => self.fg = 7,
+                value @ 40..=47 => self.bg = ansi_to_vga(value - 40),
+                49 => self.bg = 0,
+                _ => {}
+            }
+        }
+    }
+
+    fn reset_attributes(&mut self) {
+        self.fg = 7;
+        self.bg = 0;
+        self.bold = false;
+        self.blink = false;
+        self.inverse = false;
+    }
+
+    fn attribute(&self, code: u8) -> Cell {
+        let mut fg = self.fg;
+        let mut bg = self.bg;
+        let mut blink = false;
+        if self.bold && !self.bright_intensity_disabled {
+            fg += 8;
+        }
+        if self.blink {
+            if self.bright_background || self.canvas.ice_colors {
+                bg += 8;
+            } else {
+                blink = true;
+            }
+        }
+        if self.inverse {
+            std::mem::swap(&mut fg, &mut bg);
+        }
+        let bright_font = self.bold && self.alt_charset;
+        let blink_font = self.blink && self.blink_font;
+        let slot = match (bright_font, blink_font) {
+            (true, true) if self.bright_blink_font => 3,
+            (_, true) => 2,
+            (true, false) => 1,
+            (false, false) => 0,
+        };
+        Cell {
+            code,
+            fg,
+            bg,
+            blink,
+            font: self.font_slots[slot],
+        }
+    }
+
+    fn blank(&self) -> Cell {
+        Cell {
+            blink: false,
+            ..self.attribute(ascii::SPACE)
+        }
+    }
+
+    fn put(&mut self, code: u8) {
+        let cell = self.attribute(code);
+        self.canvas.set(self.x, self.y, cell);
+        if self.x + 1 == self.canvas.width {
+            self.x = 0;
+            self.y += 1;
+        } else {
+            self.x += 1;
+        }
+    }
+
+    fn move_down(&mut self, count: usize) {
+        if self.y < MAX_ROWS - 1 {
+            self.y = self.y.saturating_add(count).min(MAX_ROWS - 1);
+        }
+    }
+
+    fn tab(&mut self) {
+        if self.x + 1 >= self.canvas.width {
+            self.x = 0;
+            self.y += 1;
+        } else {
+            self.x = ((self.x / TAB_WIDTH + 1) * TAB_WIDTH).min(self.canvas.width - 1);
+        }
+    }
+
+    fn erase_in_page(&mut self, mode: usize) {
+        let blank = self.blank();
+        match mode {
+            0 => {
+                self.canvas
+                    .fill_row(self.y, self.x, self.canvas.width, blank);
+                for y in self.y + 1..self.canvas.height {
+                    self.canvas.fill_row(y, 0, self.canvas.width, blank);
+                }
+            }
+            1 => {
+                for y in 0..self.y {
+                    self.canvas.fill_row(y, 0, self.canvas.width, blank);
+                }
+                self.canvas.fill_row(self.y, 0, self.x + 1, blank);
+            }
+            2 => {
+                self.canvas.clear();
+                self.x = 0;
+                self.y = 0;
+            }
+            _ => {}
+        }
+    }
+
+    fn erase_in_line(&mut self, mode: usize) {
+        let blank = self.blank();
+        match mode {
+            0 => self
+                .canvas
+                .fill_row(self.y, self.x, self.canvas.width, blank),
+            1 => self.canvas.fill_row(self.y, 0, self.x + 1, blank),
+            2 => self.canvas.fill_row(self.y, 0, self.canvas.width, blank),
+            _ => {}
+        }
+    }
+
+    fn insert_chars(&mut self, count: usize) {
+        let blank = self.blank();
+        self.canvas.grow(self.y + 1);
+        for x in (self.x..self.canvas.width).rev() {
+            let cell = match x.checked_sub(count) {
+                Some(from_x) if from_x >= self.x => *self.canvas.get(from_x, self.y).unwrap(),
+                _ => blank,
+            };
+            self.canvas.set(x, self.y, cell);
+        }
+    }
+
+    fn delete_chars(&mut self, count: usize) {
+        let blank = self.blank();
+        self.canvas.grow(self.y + 1);
+        for x in self.x..self.canvas.width {
+            let cell = match self.canvas.get(x.saturating_add(count), self.y) {
+                Some(cell) => *cell,
+                None => blank,
+            };
+            self.canvas.set(x, self.y, cell);
+        }
+    }
+}
+
+fn ansi_to_vga(color: usize) -> u8 {
+    match color {
+        0 => 0,
+        1 => 4,
+        2 => 2,
+        3 => 6,
+        4 => 1,
+        5 => 5,
+        6 => 3,
+        _ => 7,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn huge_cursor_down_does_not_overflow() {
+        let ansi = Ansi::from_bytes(b"A\x1b[99999999999999999999999BX");
+        assert_eq!(ansi.canvas.height, MAX_ROWS);
+        assert_eq!(ansi.canvas.get(1, MAX_ROWS - 1).unwrap().code, b'X');
+    }
+
+    #[test]
+    fn cursor_moves_are_clamped() {
+        let ansi = Ansi::from_bytes(b"\x1b[99999999B\x1b[99999999EX\x1b[99999999;1HY");
+        assert_eq!(ansi.canvas.height, MAX_ROWS);
+        assert_eq!(ansi.canvas.get(0, MAX_ROWS - 1).unwrap().code, b'Y');
+    }
+
+    #[test]
+    fn insert_lines_is_clamped() {
+        let ansi = Ansi::from_bytes(b"A\x1b[99999999LB");
+        assert_eq!(ansi.canvas.height, MAX_ROWS);
+        assert_eq!(ansi.canvas.get(1, 0).unwrap().code, b'B');
+        assert!(ansi.canvas.cells.iter().all(|cell| cell.code != b'A'));
+        let ansi = Ansi::from_bytes(b"A\x1b[9998BC\x1b[1;1H\x1b[2L");
+        assert_eq!(ansi.canvas.height, MAX_ROWS);
+        assert_eq!(ansi.canvas.get(0, 2).unwrap().code, b'A');
+        assert!(ansi.canvas.cells.iter().all(|cell| cell.code != b'C'));
+    }
+
+    #[test]
+    fn zero_width_is_clamped() {
+        let mut ansi = Ansi::new(0);
+        ansi.interpret(b"AB\x1b[5C\tC");
+        assert_eq!(ansi.canvas.width, 1);
+        assert_eq!(ansi.canvas.get(0, 0).unwrap().code, b'A');
+        assert_eq!(ansi.cursor().0, 0);
+    }
+
+    fn cell(ansi: &Ansi, x: usize, y: usize) -> Cell {
+        *ansi.canvas.get(x, y).unwrap()
+    }
+
+    #[test]
+    fn graphic_rendition() {
+        let ansi = Ansi::from_bytes(b"\x1b[1;31;44mA\x1b[0;7;32mB\x1b[5mC");
+        let (a, b, c) = (cell(&ansi, 0, 0), cell(&ansi, 1, 0), cell(&ansi, 2, 0));
+        assert_eq!((a.fg, a.bg, a.blink), (12, 1, false));
+        assert_eq!((b.fg, b.bg), (0, 2));
+        assert!(c.blink);
+    }
+
+    #[test]
+    fn bright_background_mode() {
+        let ansi = Ansi::from_bytes(b"\x1b[?33h\x1b[5;41mA\x1b[?33lB");
+        let (a, b) = (cell(&ansi, 0, 0), cell(&ansi, 1, 0));
+        assert_eq!((a.bg, a.blink), (12, false));
+        assert_eq!((b.bg, b.blink), (4, true));
+    }
+
+    #[test]
+    fn font_slots() {
+        let ansi = Ansi::from_bytes(b"A\x1b[0;36 DB\x1b[1;42 D\x1b[?31h\x1b[1mC\x1b[0mD");
+        let fonts: Vec<Option<u8>> = (0..4).map(|x| cell(&ansi, x, 0).font).collect();
+        assert_eq!(fonts, [None, Some(36), Some(42), Some(36)]);
+    }
+
+    #[test]
+    fn cursor_positioning() {
+        let ansi = Ansi::from_bytes(b"\x1b[3;5HA\x1b[sB\x1b[1;1HC\x1b[uD\x1b[2CE\x1b[200CF");
+        assert_eq!(cell(&ansi, 4, 2).code, b'A');
+        assert_eq!(cell(&ansi, 5, 2).code, b'D');
+        assert_eq!(cell(&ansi, 0, 0).code, b'C');
+        assert_eq!(cell(&ansi, 8, 2).code, b'E');
+        assert_eq!(cell(&ansi, 79, 2).code, b'F');
+        assert_eq!(ansi.cursor(), (0, 3));
+    }
+
+    #[test]
+    fn editing_sequences() {
+        let ansi = Ansi::from_bytes(
+            b"ABCDEF\x1b[1;3H\x1b[2@\r\n123456\x1b[2;2H\x1b[2P\r\nxyz\x1b[2D\x1b[K",
+        );
+        let row = |y: usize| -> Vec<u8> { (0..8).map(|x| cell(&ansi, x, y).code).collect() };
+        assert_eq!(row(0), b"AB  CDEF");
+        assert_eq!(row(1), b"1456    ");
+        assert_eq!(row(2), b"x       ");
+    }
+
+    #[test]
+    fn sauce_width_and_ice_colors() {
+        let mut bytes = b"\x1b[5;41mA".to_vec();
+        let mut sauce = Sauce::new();
+        sauce.datatype = Some(DataType::Character);
+        sauce.filetype = Some(FileType::ANSI);
+        sauce.info_1 = 132;
+        sauce.ice_colors = true;
+        sauce.add_to_bytes(&mut bytes).unwrap();
+        let ansi = Ansi::from_bytes(&bytes);
+        assert_eq!(ansi.canvas.width, 132);
+        assert_eq!(cell(&ansi, 0, 0).bg, 12);
+    }
+}
@@ -0,0 +1,311 @@
+use crate::bytes::{write_bytes_to_file, PackToBytes};
+use crate::sauce::{DataType, FileType, Sauce};
+use crate::Result;
+
+static SHIFT_IN: u8 = 0x0e;
+static MUSIC_CHARS: &[u8] = b"aAbBcCdDeEfFgGlLmMnNoOpPsStT0123456789.-+#<> ";
+static LOWEST_C: f64 = 65.406;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Articulation {
+    Normal,
+    Legato,
+    Staccato,
+}
+
+#[derive(Clone, Copy)]
+pub struct Note {
+    pub pitch: Option<u8>,
+    pub duration: f64,
+    pub articulation: Articulation,
+}
+
+impl Note {
+    pub fn frequency(&self) -> Option<f64> {
+        self.pitch
+            .map(|pitch| LOWEST_C * 2f64.powf(pitch as f64 / 12.0))
+    }
+
+    pub fn sounding_duration(&self) -> f64 {
+        match self.articulation {
+            Articulation::Normal => self.duration * 7.0 / 8.0,
+            Articulation::Legato => self.duration,
+            Articulation::Staccato => self.duration * 3.0 / 4.0,
+        }
+    }
+}
+
+pub struct Music {
+    pub notes: Vec<Note>,
+    pub background: bool,
+}
+
+struct MusicParser<'a> {
+    bytes: &'a [u8],
+    i: usize,
+    tempo: usize,
+    octave: usize,
+    length: usize,
+    articulation: Articulation,
+    background: bool,
+}
+
+impl<'a> MusicParser<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.i).map(|byte| byte.to_ascii_uppercase())
+    }
+
+    fn number(&mut self) -> Option<usize> {
+        let mut value: Option<usize> = None;
+        while let Some(byte @ b'0'..=b'9') = self.peek() {
+            let digit = (byte - b'0') as usize;
+            value = Some(value.unwrap_or(0).saturating_mul(10).saturating_add(digit));
+            self.i += 1;
+        }
+        value
+    }
+
+    fn note(&mut self, pitch: Option<isize>) -> Note {
+        let mut pitch = pitch;
+        let mut length = self.length;
+        let mut dotted = false;
+        loop {
+            match self.peek() {
+                Some(b'+') | Some(b'#') => {
+                    pitch = pitch.map(|pitch| pitch + 1);
+                    self.i += 1;
+                }
+                Some(b'-') => {
+                    pitch = pitch.map(|pitch| pitch - 1);
+                    self.i += 1;
+                }
+                Some(b'.') => {
+                    dotted = true;
+                    self.i += 1;
+                }
+                Some(b'0'..=b'9') => {
+                    if let Some(value) = self.number() {
+                        if (1..=64).contains(&value) {
+                            length = value;
+                        }
+                    }
+                }
+                _ => break,
+            }
+        }
+        let mut duration = 240.0 / self.tempo as f64 / length as f64;
+        if dotted {
+            duration *= 1.5;
+        }
+        Note {
+            pitch: pitch.and_then(|pitch| {
+                if (0..84).contains(&pitch) {
+                    Some(pitch as u8)
+                } else {
+                    None
+                }
+            }),
+            duration,
+            articulation: self.articulation,
+        }
+    }
+
+    fn parse(&mut self) -> Vec<Note> {
+        let mut notes = Vec::new();
+        while let Some(byte) = self.peek() {
+            self.i += 1;
+            match byte {
+                b'M' => {
+                    match self.peek() {
+                        Some(b'F') => self.background = false,
+                        Some(b'B') => self.background = true,
+                        Some(b'N') => self.articulation = Articulation::Normal,
+                        Some(b'L') => self.articulation = Articulation::Legato,
+                        Some(b'S') => self.articulation = Articulation::Staccato,
+                        _ => continue,
+                    }
+                    self.i += 1;
+                }
+                b'T' => {
+                    if let Some(tempo) = self.number() {
+                        self.tempo = tempo.clamp(32, 255);
+                    }
+                }
+                b'O' => {
+                    if let Some(octave) = self.number() {
+                        self.octave = octave.min(6);
+                    }
+                }
+                b'L' => {
+                    if let Some(length) = self.number() {
+                        if (1..=64).contains(&length) {
+                            self.length = length;
+                        }
+                    }
+                }
+                b'<' => self.octave = self.octave.saturating_sub(1),
+                b'>' => self.octave = (self.octave + 1).min(6),
+                b'N' => {
+                    let pitch = self
+                        .number()
+                        .and_then(|pitch| pitch.min(84).checked_sub(1))
+                        .map(|pitch| pitch as isize);
+                    notes.push(self.note(pitch));
+                }
+                b'P' => notes.push(self.note(None)),
+                b'A'..=b'G' => {
+                    let semitone = match byte {
+                        b'C' => 0,
+                        b'D' => 2,
+                        b'E' => 4,
+                        b'F' => 5,
+                        b'G' => 7,
+                        b'A' => 9,
+                        _ => 11,
+                    };
+                    let pitch = (self.octave * 12) as isize + semitone;
+                    notes.push(self.note(Some(pitch)));
+                }
+                _ => {}
+            }
+        }
+        notes
+    }
+}
+
+impl Music {
+    pub fn from_bytes(bytes: &[u8], implied_m: bool) -> Option<(Music, usize)> {
+        let end = bytes.iter().position(|byte| *byte == SHIFT_IN)?;
+        let string = &bytes[0..end];
+        if !string.iter().all(|byte| MUSIC_CHARS.contains(byte)) {
+            return None;
+        }
+        let mut bytes = string.to_vec();
+        if implied_m {
+            bytes.insert(0, b'M');
+        }
+        let mut parser = MusicParser {
+            bytes: &bytes,
+            i: 0,
+            tempo: 120,
+            octave: 4,
+            length: 4,
+            articulation: Articulation::Normal,
+            background: false,
+        };
+        let notes = parser.parse();
+        let music = Music {
+            notes,
+            background: parser.background,
+        };
+        Some((music, end + 1))
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.notes.iter().map(|note| note.duration).sum()
+    }
+
+    pub fn to_wav_bytes(&self, sample_rate: usize) -> Vec<u8> {
+        let mut samples = Vec::new();
+        for note in self.notes.iter() {
+            let total = (note.duration * sample_rate as f64).round() as usize;
+            let sounding = match note.frequency() {
+                Some(_) => (note.sounding_duration() * sample_rate as f64).round() as usize,
+                None => 0,
+            };
+            let frequency = note.frequency().unwrap_or(0.0);
+            for sample in 0..total {
+                if sample < sounding {
+                    let phase = (sample as f64 * frequency / sample_rate as f64).fract();
+                    samples.push(if phase < 0.5 { 0xc0 } else { 0x40 });
+                } else {
+                    samples.push(0x80);
+                }
+            }
+        }
+        let mut bytes = vec![0; 44];
+        bytes[0..4].copy_from_slice(b"RIFF");
+        (36 + samples.len() + samples.len() % 2).pack_to_bytes(&mut bytes[4..8]);
+        bytes[8..16].copy_from_slice(b"WAVEfmt ");
+        16usize.pack_to_bytes(&mut bytes[16..20]);
+        1usize.pack_to_bytes(&mut bytes[20..22]);
+        1usize.pack_to_bytes(&mut bytes[22..24]);
+        sample_rate.pack_to_bytes(&mut bytes[24..28]);
+        sample_rate.pack_to_bytes(&mut bytes[28..32]);
+        1usize.pack_to_bytes(&mut bytes[32..34]);
+        8usize.pack_to_bytes(&mut bytes[34..36]);
+        bytes[36..40].copy_from_slice(b"data");
+        samples.len().pack_to_bytes(&mut bytes[40..44]);
+        bytes.append(&mut samples);
+        if bytes.len() % 2 == 1 {
+            bytes.push(0);
+        }
+        bytes
+    }
+
+    pub fn as_wav_file(&self, file: &str, sample_rate: usize) -> Result<()> {
+        let mut bytes = self.to_wav_bytes(sample_rate);
+        let mut sauce = Sauce::new();
+        sauce.datatype = Some(DataType::Audio);
+        sauce.filetype = Some(FileType::WAV);
+        sauce.add_to_bytes(&mut bytes)?;
+        write_bytes_to_file(&bytes, file)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pitches(bytes: &[u8]) -> Vec<Option<u8>> {
+        let (music, _) = Music::from_bytes(bytes, false).expect("valid music string");
+        music.notes.iter().map(|note| note.pitch).collect()
+    }
+
+    #[test]
+    fn numbered_notes() {
+        assert_eq!(
+            pitches(b"N0N1N49N84N99\x0e"),
+            vec![None, Some(0), Some(48), Some(83), Some(83)]
+        );
+    }
+
+    #[test]
+    fn numbered_notes_match_named_notes() {
+        assert_eq!(pitches(b"N1\x0e"), pitches(b"O0C\x0e"));
+        assert_eq!(pitches(b"N49\x0e"), pitches(b"O4C\x0e"));
+        assert_eq!(pitches(b"N84\x0e"), pitches(b"O6B\x0e"));
+    }
+
+    #[test]
+    fn rest_lasts_a_note_length() {
+        let (music, consumed) = Music::from_bytes(b"T120L4N0\x0e", false).unwrap();
+        assert_eq!(consumed, 9);
+        assert_eq!(music.notes.len(), 1);
+        assert!(music.notes[0].frequency().is_none());
+        assert!((music.duration() - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn extracted_from_ansi() {
+        let ansi = crate::ansi::Ansi::from_bytes(b"A\x1b[MFT120O4C\x0eB");
+        assert_eq!(ansi.music.len(), 1);
+        assert_eq!(ansi.music[0].notes.len(), 1);
+        assert!(!ansi.music[0].background);
+        assert_eq!(ansi.canvas.get(1, 0).unwrap().code, b'B');
+    }
+
+    #[test]
+    fn wav_synthesis() {
+        let (music, _) = Music::from_bytes(b"T120L4CP4\x0e", false).unwrap();
+        let wav = music.to_wav_bytes(8000);
+        assert_eq!(&wav[0..4], b"RIFF");
+        assert_eq!(&wav[8..16], b"WAVEfmt ");
+        assert_eq!(&wav[36..40], b"data");
+        let samples = &wav[44..44 + 8000];
+        assert_eq!(samples.len(), 8000);
+        assert!(samples[..3500].iter().any(|sample| *sample != 0x80));
+        assert!(samples[3500..].iter().all(|sample| *sample == 0x80));
+    }
+}
@@ -0,0 +1,245 @@
+use crate::bytes::read_file_to_bytes;
+use crate::error::{Context, Result};
+use crate::sauce::Sauce;
+use crate::Error;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek};
+use std::path::Path;
+use zip::result::ZipError;
+use zip::ZipArchive;
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ArchiveError {
+    InvalidArchive(&'static str),
+    UnsupportedArchive(&'static str),
+    EntryNotFound,
+    DuplicateEntry,
+}
+
+impl std::fmt::Display for ArchiveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ArchiveError::InvalidArchive(reason) => write!(f, "Invalid ZIP archive: {}", reason),
+            ArchiveError::UnsupportedArchive(reason) => {
+                write!(f, "Unsupported ZIP archive: {}", reason)
+            }
+            ArchiveError::EntryNotFound => write!(f, "Entry not found in ZIP archive"),
+            ArchiveError::DuplicateEntry => write!(f, "Duplicate entry name in ZIP archive"),
+        }
+    }
+}
+
+impl std::error::Error for ArchiveError {}
+
+impl From<ZipError> for Error {
+    fn from(error: ZipError) -> Error {
+        match error {
+            ZipError::Io(error) => Error::from(error),
+            ZipError::InvalidArchive(reason) => Error::from(ArchiveError::InvalidArchive(reason)),
+            ZipError::UnsupportedArchive(reason) => {
+                Error::from(ArchiveError::UnsupportedArchive(reason))
+            }
+            ZipError::FileNotFound => Error::from(ArchiveError::EntryNotFound),
+        }
+    }
+}
+
+pub struct Archive<R: Read + Seek> {
+    zip: ZipArchive<R>,
+    name: String,
+}
+
+impl Archive<BufReader<File>> {
+    pub fn from_file<P: AsRef<Path>>(file: P) -> Result<Archive<BufReader<File>>> {
+        let reader = File::open(&file).with_path(&file)?;
+        let name = file.as_ref().display().to_string();
+        Archive::from_reader(BufReader::new(reader), &name).with_path(&file)
+    }
+}
+
+impl<R: Read + Seek> Archive<R> {
+    pub fn from_reader(reader: R, name: &str) -> Result<Archive<R>> {
+        let zip = ZipArchive::new(reader)?;
+        Ok(Archive {
+            zip,
+            name: name.to_string(),
+        })
+    }
+
+    pub fn comment(&self) -> &[u8] {
+        self.zip.comment()
+    }
+
+    pub fn entries(&mut self) -> Result<Vec<String>> {
+        let mut entries = Vec::with_capacity(self.zip.len());
+        for i in 0..self.zip.len() {
+            let entry = self.zip.by_index(i)?;
+            if entry.is_file() {
+                entries.push(entry.name().to_string());
+            }
+        }
+        Ok(entries)
+    }
+
+    pub fn key(&self, entry: &str) -> String {
+        format!("{}/{}", self.name, entry)
+    }
+
+    pub fn read_entry(&mut self, entry: &str) -> Result<Vec<u8>> {
+        let key = self.key(entry);
+        let mut bytes = Vec::new();
+        self.zip
+            .by_name(entry)
+            .with_path(&key)?
+            .read_to_end(&mut bytes)
+            .with_path(&key)?;
+        Ok(bytes)
+    }
+
+    pub fn sauce(&mut self, entry: &str) -> Result<Option<Sauce>> {
+        let key = self.key(entry);
+        let bytes = self.read_entry(entry)?;
+        match Sauce::from_bytes(&bytes).with_path(&key)? {
+            Some(mut sauce) => {
+                sauce.file = Some(key);
+                Ok(Some(sauce))
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn sauces(&mut self) -> Result<Vec<(String, Result<Option<Sauce>>)>> {
+        let mut sauces = Vec::new();
+        for entry in self.entries()? {
+            let sauce = self.sauce(&entry);
+            sauces.push((self.key(&entry), sauce));
+        }
+        Ok(sauces)
+    }
+}
+
+pub fn is_archive<P: AsRef<Path>>(file: P) -> bool {
+    file.as_ref()
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map_or(false, |extension| extension.eq_ignore_ascii_case("zip"))
+}
+
+pub fn split_entry_path(path: &str) -> Option<(&str, &str)> {
+    path.match_indices('/').find_map(|(i, _)| {
+        let (archive, entry) = (&path[..i], &path[i + 1..]);
+        if is_archive(archive) && Path::new(archive).is_file() && !entry.is_empty() {
+            Some((archive, entry))
+        } else {
+            None
+        }
+    })
+}
+
+pub fn read_file_or_entry<P: AsRef<Path>>(path: P) -> Result<Vec<u8>> {
+    match path.as_ref().to_str().and_then(split_entry_path) {
+        Some((archive, entry)) => Archive::from_file(archive)?.read_entry(entry),
+        None => read_file_to_bytes(&path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Write};
+    use zip::write::FileOptions;
+    use zip::ZipWriter;
+
+    fn zip_bytes() -> Vec<u8> {
+        let mut sauce = Sauce::new();
+        sauce.title = "inside".to_string();
+        let mut art = b"art".to_vec();
+        sauce.add_to_bytes(&mut art).unwrap();
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer.set_comment("pack comment");
+        writer
+            .add_directory("ART/", FileOptions::default())
+            .unwrap();
+        writer
+            .start_file("ART/ONE.ANS", FileOptions::default())
+            .unwrap();
+        writer.write_all(&art).unwrap();
+        writer
+            .start_file("FILE_ID.DIZ", FileOptions::default())
+            .unwrap();
+        writer.write_all(b"plain").unwrap();
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn entries_and_sauces() {
+        let mut archive = Archive::from_reader(Cursor::new(zip_bytes()), "PACK.ZIP").unwrap();
+        assert_eq!(archive.comment(), b"pack comment");
+        assert_eq!(archive.entries().unwrap(), ["ART/ONE.ANS", "FILE_ID.DIZ"]);
+        assert_eq!(archive.read_entry("FILE_ID.DIZ").unwrap(), b"plain");
+        let sauce = archive.sauce("ART/ONE.ANS").unwrap().unwrap();
+        assert_eq!(sauce.title, "inside");
+        assert_eq!(sauce.file.as_deref(), Some("PACK.ZIP/ART/ONE.ANS"));
+        let sauces = archive.sauces().unwrap();
+        assert_eq!(sauces.len(), 2);
+        assert_eq!(sauces[1].0, "PACK.ZIP/FILE_ID.DIZ");
+        assert!(sauces[1].1.as_ref().unwrap().is_none());
+    }
+
+    #[test]
+    fn missing_entries_and_invalid_archives() {
+        let mut archive = Archive::from_reader(Cursor::new(zip_bytes()), "PACK.ZIP").unwrap();
+        let error = archive.read_entry("MISSING.ANS").unwrap_err();
+        assert!(matches!(
+            error,
+            Error::Archive {
+                kind: ArchiveError::EntryNotFound,
+                ..
+            }
+        ));
+        assert_eq!(error.path(), Some(Path::new("PACK.ZIP/MISSING.ANS")));
+        let error = Archive::from_reader(Cursor::new(b"not a zip".to_vec()), "BAD.ZIP")
+            .err()
+            .unwrap();
+        assert!(matches!(
+            error,
+            Error::Archive {
+                kind: ArchiveError::InvalidArchive(_),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn entry_paths() {
+        assert!(is_archive("pack.ZIP"));
+        assert!(!is_archive("art.ans"));
+        assert!(is_archive(Path::new("art").join("pack.zip")));
+        let directory = std::env::temp_dir().join(format!("tmode-archive-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let file = directory.join("PACK.ZIP");
+        let zip = zip_bytes();
+        std::fs::write(&file, &zip).unwrap();
+        let path = format!("{}/ART/ONE.ANS", file.display());
+        assert_eq!(
+            read_file_or_entry(file.join("FILE_ID.DIZ")).unwrap(),
+            b"plain"
+        );
+        assert_eq!(
+            Archive::from_file(&file).unwrap().entries().unwrap().len(),
+            2
+        );
+        let file = file.display().to_string();
+        assert_eq!(
+            split_entry_path(&path),
+            Some((file.as_str(), "ART/ONE.ANS"))
+        );
+        assert_eq!(split_entry_path(&format!("{}/", file)), None);
+        assert_eq!(split_entry_path("missing.zip/ART.ANS"), None);
+        let bytes = read_file_or_entry(format!("{}/FILE_ID.DIZ", file)).unwrap();
+        assert_eq!(bytes, b"plain");
+        assert_eq!(read_file_or_entry(&file).unwrap(), zip);
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,182 @@
+use crate::bytes::read_file_to_bytes;
+use crate::canvas::{Canvas, Cell};
+use crate::Result;
+
+static ESC: u8 = 0x1b;
+static CURSOR_UP: u8 = 0x1c;
+static CURSOR_DOWN: u8 = 0x1d;
+static CURSOR_LEFT: u8 = 0x1e;
+static CURSOR_RIGHT: u8 = 0x1f;
+static CLEAR_SCREEN: u8 = 0x7d;
+static BACKSPACE: u8 = 0x7e;
+static TAB: u8 = 0x7f;
+static EOL: u8 = 0x9b;
+static DELETE_LINE: u8 = 0x9c;
+static INSERT_LINE: u8 = 0x9d;
+static BELL: u8 = 0xfd;
+static DELETE_CHAR: u8 = 0xfe;
+static INSERT_CHAR: u8 = 0xff;
+static TAB_WIDTH: usize = 8;
+pub static ATASCII_WIDTH: usize = 40;
+
+pub struct Atascii {
+    pub canvas: Canvas,
+    x: usize,
+    y: usize,
+}
+
+impl Atascii {
+    pub fn new() -> Atascii {
+        Atascii {
+            canvas: Canvas::new(ATASCII_WIDTH, 0),
+            x: 0,
+            y: 0,
+        }
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Atascii {
+        let mut atascii = Atascii::new();
+        atascii.interpret(bytes);
+        atascii
+    }
+
+    pub fn from_file(file: &str) -> Result<Atascii> {
+        let bytes = read_file_to_bytes(file)?;
+        Ok(Atascii::from_bytes(&bytes))
+    }
+
+    pub fn interpret(&mut self, bytes: &[u8]) {
+        let mut literal = false;
+        for &byte in bytes {
+            if literal {
+                literal = false;
+                self.put(byte);
+                continue;
+            }
+            match byte {
+                _ if byte == ESC => literal = true,
+                _ if byte == CURSOR_UP => self.y = self.y.saturating_sub(1),
+                _ if byte == CURSOR_DOWN => self.y += 1,
+                _ if byte == CURSOR_LEFT => {
+                    self.x = self.x.checked_sub(1).unwrap_or(ATASCII_WIDTH - 1)
+                }
+                _ if byte == CURSOR_RIGHT => self.x = (self.x + 1) % ATASCII_WIDTH,
+                _ if byte == CLEAR_SCREEN => {
+                    self.canvas.clear();
+                    self.x = 0;
+                    self.y = 0;
+                }
+                _ if byte == BACKSPACE => {
+                    if self.x > 0 {
+                        self.x -= 1;
+                        self.canvas.set(self.x, self.y, Cell::default());
+                    }
+                }
+                _ if byte == TAB => {
+                    self.x = ((self.x / TAB_WIDTH + 1) * TAB_WIDTH).min(ATASCII_WIDTH - 1)
+                }
+                _ if byte == EOL => {
+                    self.x = 0;
+                    self.y += 1;
+                }
+                _ if byte == DELETE_LINE => self.canvas.remove_rows(self.y, 1),
+                _ if byte == INSERT_LINE => self.canvas.insert_rows(self.y, 1, Cell::default()),
+                _ if byte == BELL => {}
+                _ if byte == DELETE_CHAR => self.delete_char(),
+                _ if byte == INSERT_CHAR => self.insert_char(),
+                _ => self.put(byte),
+            }
+        }
+    }
+
+    fn put(&mut self, code: u8) {
+        let cell = Cell {
+            code,
+            ..Default::default()
+        };
+        self.canvas.set(self.x, self.y, cell);
+        if self.x + 1 == ATASCII_WIDTH {
+            self.x = 0;
+            self.y += 1;
+        } else {
+            self.x += 1;
+        }
+    }
+
+    fn delete_char(&mut self) {
+        self.canvas.grow(self.y + 1);
+        for x in self.x..ATASCII_WIDTH {
+            let cell = match self.canvas.get(x + 1, self.y) {
+                Some(cell) => *cell,
+                None => Cell::default(),
+            };
+            self.canvas.set(x, self.y, cell);
+        }
+    }
+
+    fn insert_char(&mut self) {
+        self.canvas.grow(self.y + 1);
+        for x in (self.x + 1..ATASCII_WIDTH).rev() {
+            let cell = *self.canvas.get(x - 1, self.y).unwrap();
+            self.canvas.set(x, self.y, cell);
+        }
+        self.canvas.set(self.x, self.y, Cell::default());
+    }
+}
+
+impl Default for Atascii {
+    fn default() -> Atascii {
+        Atascii::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::font::Font;
+
+    fn row(atascii: &Atascii, y: usize, length: usize) -> Vec<u8> {
+        (0..length)
+            .map(|x| atascii.canvas.get(x, y).unwrap().code)
+            .collect()
+    }
+
+    #[test]
+    fn end_of_line_and_wrapping() {
+        let mut bytes = b"HI\x9b".to_vec();
+        bytes.extend(std::iter::repeat(b'X').take(ATASCII_WIDTH + 1));
+        let atascii = Atascii::from_bytes(&bytes);
+        assert_eq!(atascii.canvas.width, ATASCII_WIDTH);
+        assert_eq!(row(&atascii, 0, 2), b"HI");
+        assert_eq!(atascii.canvas.get(ATASCII_WIDTH - 1, 1).unwrap().code, b'X');
+        assert_eq!(atascii.canvas.get(0, 2).unwrap().code, b'X');
+    }
+
+    #[test]
+    fn escape_prints_controls() {
+        let atascii = Atascii::from_bytes(b"A\x1b\x7dB\x1b\x9b");
+        assert_eq!(row(&atascii, 0, 4), [b'A', 0x7d, b'B', 0x9b]);
+    }
+
+    #[test]
+    fn cursor_and_clear() {
+        let atascii = Atascii::from_bytes(b"GONE\x7dAB\x1eC\x1f\x1fD\x1dE\x1c\x1cF");
+        assert_eq!(row(&atascii, 0, 7), b"AC  D F");
+        assert_eq!(atascii.canvas.get(5, 1).unwrap().code, b'E');
+    }
+
+    #[test]
+    fn editing() {
+        let atascii = Atascii::from_bytes(b"ABCD\x1e\x1e\xffX\x1e\x1e\xfe\x7eZ");
+        assert_eq!(row(&atascii, 0, 5), b"ZXCD ");
+    }
+
+    #[test]
+    fn inverse_video_glyphs() {
+        let font: Font = "Atari ATASCII".parse().unwrap();
+        assert_eq!((font.width, font.height, font.length), (8, 8, 256));
+        let normal = &font.bitmasks[b'A' as usize];
+        let inverse = &font.bitmasks[b'A' as usize | 0x80];
+        assert!(normal.iter().zip(inverse.iter()).all(|(a, b)| a != b));
+    }
+}
@@ -0,0 +1,300 @@
+mod pcboard;
+mod pipe;
+mod wildcat;
+mod wwiv;
+pub use self::pcboard::PCBoard;
+pub use self::pipe::Pipe;
+pub use self::wildcat::Wildcat;
+pub use self::wwiv::Wwiv;
+use crate::ascii;
+use crate::bytes::{read_file_to_bytes, write_bytes_to_file};
+use crate::canvas::{Canvas, Cell};
+use crate::sauce::{DataType, FileType, Sauce};
+use crate::Result;
+
+static DEFAULT_WIDTH: usize = 80;
+static TAB_WIDTH: usize = 8;
+
+pub struct Screen {
+    pub canvas: Canvas,
+    pub x: usize,
+    pub y: usize,
+    pub fg: u8,
+    pub bg: u8,
+    pub blink: bool,
+}
+
+impl Screen {
+    pub fn new(width: usize) -> Screen {
+        Screen {
+            canvas: Canvas::new(width, 0),
+            x: 0,
+            y: 0,
+            fg: 7,
+            bg: 0,
+            blink: false,
+        }
+    }
+
+    pub fn put(&mut self, code: u8) {
+        let cell = Cell {
+            code,
+            fg: self.fg,
+            bg: self.bg,
+            blink: self.blink,
+            font: None,
+        };
+        self.canvas.set(self.x, self.y, cell);
+        if self.x + 1 == self.canvas.width {
+            self.new_line();
+        } else {
+            self.x += 1;
+        }
+    }
+
+    pub fn new_line(&mut self) {
+        self.x = 0;
+        self.y += 1;
+    }
+
+    pub fn control(&mut self, byte: u8) -> bool {
+        match byte {
+            b'\r' => self.x = 0,
+            b'\n' => self.new_line(),
+            b'\t' => self.x = ((self.x / TAB_WIDTH + 1) * TAB_WIDTH).min(self.canvas.width - 1),
+            0x08 => self.x = self.x.saturating_sub(1),
+            _ => return false,
+        }
+        true
+    }
+
+    pub fn set_attribute(&mut self, attribute: u8) {
+        self.fg = attribute & 15;
+        self.bg = (attribute >> 4) & 7;
+        self.blink = false;
+        if attribute & 128 != 0 {
+            if self.canvas.ice_colors {
+                self.bg += 8;
+            } else {
+                self.blink = true;
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.canvas.clear();
+        self.x = 0;
+        self.y = 0;
+    }
+}
+
+fn screen_from_sauce(bytes: &[u8]) -> Screen {
+    let mut width = DEFAULT_WIDTH;
+    let mut ice_colors = false;
+    if let Ok(Some(sauce)) = Sauce::from_bytes(bytes) {
+        if let (Some(DataType::Character), Some(FileType::ASCII))
+        | (Some(DataType::Character), Some(FileType::ANSI))
+        | (Some(DataType::Character), Some(FileType::ANSImation))
+        | (Some(DataType::Character), Some(FileType::PCBoard)) =
+            (&sauce.datatype, &sauce.filetype)
+        {
+            if sauce.info_1 > 0 {
+                width = sauce.info_1;
+            }
+        }
+        ice_colors = sauce.ice_colors;
+    }
+    let mut screen = Screen::new(width);
+    screen.canvas.ice_colors = ice_colors;
+    screen
+}
+
+fn is_blank(cell: &Cell) -> bool {
+    (cell.code == ascii::SPACE || cell.code == ascii::NULL) && cell.bg == 0 && !cell.blink
+}
+
+pub fn encode_canvas<M: MacroColor>(canvas: &Canvas) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let mut literals = Vec::new();
+    let mut current = Cell::default();
+    for y in 0..canvas.height {
+        let row = &canvas.cells[y * canvas.width..(y + 1) * canvas.width];
+        let length = match row.iter().rposition(|cell| !is_blank(cell)) {
+            Some(x) => x + 1,
+            None => 0,
+        };
+        for cell in row.iter().take(length) {
+            let fg_changed = cell.fg != current.fg && cell.code != ascii::SPACE;
+            if fg_changed || cell.bg != current.bg || cell.blink != current.blink {
+                let mut next = *cell;
+                if !fg_changed {
+                    next.fg = current.fg;
+                }
+                let mut code = M::encode(&current, &next);
+                literals.resize(literals.len() + code.len(), None);
+                bytes.append(&mut code);
+                current = next;
+            }
+            bytes.push(match cell.code {
+                0x08 | b'\t' | b'\n' | b'\r' | 0x1a | 0x1b => ascii::SPACE,
+                code => code,
+            });
+            literals.push(Some(current));
+        }
+        if length < canvas.width && y + 1 < canvas.height {
+            bytes.extend_from_slice(b"\r\n");
+            literals.extend_from_slice(&[None, None]);
+        }
+    }
+    escape_literals::<M>(&bytes, &literals, canvas.width.max(1))
+}
+
+fn escape_literals<M: MacroColor>(
+    bytes: &[u8],
+    literals: &[Option<Cell>],
+    width: usize,
+) -> Vec<u8> {
+    let mut screen = Screen::new(width);
+    let mut escaped = Vec::with_capacity(bytes.len());
+    for (i, (byte, literal)) in bytes.iter().zip(literals.iter()).enumerate() {
+        escaped.push(*byte);
+        if let Some(current) = literal {
+            if M::decode(&mut screen, &bytes[i..]).is_some() {
+                escaped.append(&mut M::escape(current));
+            }
+        }
+    }
+    escaped
+}
+
+pub trait MacroColor {
+    fn decode(screen: &mut Screen, bytes: &[u8]) -> Option<usize>;
+
+    fn encode(current: &Cell, cell: &Cell) -> Vec<u8>;
+
+    fn escape(current: &Cell) -> Vec<u8> {
+        Self::encode(current, current)
+    }
+
+    fn filetype() -> FileType {
+        FileType::ASCII
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Canvas {
+        let mut screen = screen_from_sauce(bytes);
+        let mut i = 0;
+        while i < bytes.len() {
+            let byte = bytes[i];
+            if byte == ascii::EOF {
+                break;
+            }
+            if let Some(length) = Self::decode(&mut screen, &bytes[i..]) {
+                i += length;
+                continue;
+            }
+            if !screen.control(byte) {
+                screen.put(byte);
+            }
+            i += 1;
+        }
+        screen.canvas
+    }
+
+    fn from_file(file: &str) -> Result<Canvas> {
+        let bytes = read_file_to_bytes(file)?;
+        Ok(Self::from_bytes(&bytes))
+    }
+
+    fn to_bytes(canvas: &Canvas) -> Result<Vec<u8>>
+    where
+        Self: Sized,
+    {
+        let mut bytes = encode_canvas::<Self>(canvas);
+        if canvas.width != DEFAULT_WIDTH || canvas.ice_colors {
+            let mut sauce = Sauce::new();
+            sauce.datatype = Some(DataType::Character);
+            sauce.filetype = Some(Self::filetype());
+            sauce.info_1 = canvas.width;
+            sauce.info_2 = canvas.height;
+            sauce.ice_colors = canvas.ice_colors;
+            sauce.add_to_bytes(&mut bytes)?;
+        }
+        Ok(bytes)
+    }
+
+    fn to_file(canvas: &Canvas, file: &str) -> Result<()>
+    where
+        Self: Sized,
+    {
+        write_bytes_to_file(&Self::to_bytes(canvas)?, file)?;
+        Ok(())
+    }
+}
+
+pub fn hex_digit(byte: u8) -> Option<u8> {
+    match byte {
+        b'0'..=b'9' => Some(byte - b'0'),
+        b'A'..=b'F' => Some(byte - b'A' + 10),
+        b'a'..=b'f' => Some(byte - b'a' + 10),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip<M: MacroColor>(canvas: &Canvas) -> Canvas {
+        M::from_bytes(&M::to_bytes(canvas).unwrap())
+    }
+
+    #[test]
+    fn sauce_width() {
+        let mut bytes = vec![b'x'; 100];
+        assert_eq!(PCBoard::from_bytes(&bytes).height, 2);
+        let mut sauce = Sauce::new();
+        sauce.datatype = Some(DataType::Character);
+        sauce.filetype = Some(FileType::PCBoard);
+        sauce.info_1 = 132;
+        sauce.add_to_bytes(&mut bytes).unwrap();
+        let canvas = PCBoard::from_bytes(&bytes);
+        assert_eq!((canvas.width, canvas.height), (132, 1));
+        sauce.filetype = Some(FileType::RIPScript);
+        sauce.add_to_bytes(&mut bytes).unwrap();
+        assert_eq!(PCBoard::from_bytes(&bytes).width, DEFAULT_WIDTH);
+    }
+
+    #[test]
+    fn wide_canvases_round_trip() {
+        let mut canvas = Canvas::new(132, 3);
+        for (i, cell) in canvas.cells.iter_mut().enumerate() {
+            cell.code = b'a' + (i % 26) as u8;
+            cell.fg = (i / 7 % 16) as u8;
+        }
+        let codes = |canvas: &Canvas| -> Vec<(u8, u8)> {
+            canvas
+                .cells
+                .iter()
+                .map(|cell| (cell.code, cell.fg))
+                .collect()
+        };
+        for decoded in [
+            round_trip::<Pipe>(&canvas),
+            round_trip::<PCBoard>(&canvas),
+            round_trip::<Wildcat>(&canvas),
+        ]
+        .iter()
+        {
+            assert_eq!((decoded.width, decoded.height), (132, 3));
+            assert_eq!(codes(decoded), codes(&canvas));
+        }
+        let bytes = PCBoard::to_bytes(&canvas).unwrap();
+        let sauce = Sauce::from_bytes(&bytes).unwrap().unwrap();
+        assert_eq!(sauce.filetype, Some(FileType::PCBoard));
+        assert_eq!((sauce.info_1, sauce.info_2), (132, 3));
+        let narrow = Canvas::new(80, 1);
+        assert!(Sauce::from_bytes(&Pipe::to_bytes(&narrow).unwrap())
+            .unwrap()
+            .is_none());
+    }
+}
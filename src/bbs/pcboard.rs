@@ -0,0 +1,83 @@
+use crate::bbs::{hex_digit, MacroColor, Screen};
+use crate::canvas::Cell;
+use crate::sauce::FileType;
+
+pub struct PCBoard;
+
+impl MacroColor for PCBoard {
+    fn filetype() -> FileType {
+        FileType::PCBoard
+    }
+
+    fn decode(screen: &mut Screen, bytes: &[u8]) -> Option<usize> {
+        match bytes {
+            [b'@', b'X', bg, fg, ..] => {
+                let attribute = (hex_digit(*bg)? << 4) | hex_digit(*fg)?;
+                screen.set_attribute(attribute);
+                Some(4)
+            }
+            [b'@', b'C', b'L', b'S', b'@', ..] => {
+                screen.clear();
+                Some(5)
+            }
+            [b'@', b'P', b'O', b'S', b':', rest @ ..] => {
+                let digits = rest.iter().take_while(|byte| byte.is_ascii_digit()).count();
+                if digits == 0 || digits > 2 || rest.get(digits) != Some(&b'@') {
+                    return None;
+                }
+                let x = rest[0..digits]
+                    .iter()
+                    .fold(0, |value, byte| value * 10 + (byte - b'0') as usize);
+                screen.x = (x.max(1) - 1).min(screen.canvas.width - 1);
+                Some(6 + digits)
+            }
+            _ => None,
+        }
+    }
+
+    fn encode(_current: &Cell, cell: &Cell) -> Vec<u8> {
+        let attribute = cell.attribute();
+        format!("@X{:X}{:X}", attribute >> 4, attribute & 15).into_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::Canvas;
+
+    fn codes(canvas: &Canvas) -> Vec<u8> {
+        canvas.cells.iter().map(|cell| cell.code).collect()
+    }
+
+    #[test]
+    fn colors() {
+        let canvas = PCBoard::from_bytes(b"@X1FA@X4eB");
+        let cells: Vec<(u8, u8, u8)> = canvas.cells[..2]
+            .iter()
+            .map(|cell| (cell.code, cell.fg, cell.bg))
+            .collect();
+        assert_eq!(cells, [(b'A', 15, 1), (b'B', 14, 4)]);
+    }
+
+    #[test]
+    fn literal_codes_round_trip() {
+        let text: &[u8] = b"a@X1Fb@CLS@c@POS:5@d@@X07e@X";
+        let mut canvas = Canvas::new(132, 1);
+        for (x, code) in text.iter().enumerate() {
+            let fg = if x < 6 { 7 } else { 11 };
+            let cell = Cell {
+                code: *code,
+                fg,
+                ..Cell::default()
+            };
+            canvas.set(x, 0, cell);
+        }
+        let decoded = PCBoard::from_bytes(&PCBoard::to_bytes(&canvas).unwrap());
+        assert_eq!(decoded.width, 132);
+        assert_eq!(codes(&decoded), codes(&canvas));
+        for (a, b) in decoded.cells.iter().zip(canvas.cells.iter()) {
+            assert_eq!((a.fg, a.bg), (b.fg, b.bg));
+        }
+    }
+}
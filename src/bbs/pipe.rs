@@ -0,0 +1,134 @@
+use crate::bbs::{MacroColor, Screen};
+use crate::canvas::Cell;
+
+pub struct Pipe;
+
+fn two_digits(bytes: &[u8]) -> Option<usize> {
+    match bytes {
+        [tens @ b'0'..=b'9', units @ b'0'..=b'9', ..] => {
+            Some(((tens - b'0') * 10 + (units - b'0')) as usize)
+        }
+        _ => None,
+    }
+}
+
+impl MacroColor for Pipe {
+    fn decode(screen: &mut Screen, bytes: &[u8]) -> Option<usize> {
+        match bytes {
+            [b'|', b'C', b'R', ..] => screen.new_line(),
+            [b'|', b'C', b'L', ..] => screen.clear(),
+            [b'|', b'[', b'X', rest @ ..] => {
+                let x = two_digits(rest)?;
+                screen.x = (x.max(1) - 1).min(screen.canvas.width - 1);
+                return Some(5);
+            }
+            [b'|', b'[', b'Y', rest @ ..] => {
+                let y = two_digits(rest)?;
+                screen.y = y.max(1) - 1;
+                return Some(5);
+            }
+            [b'|', rest @ ..] => match two_digits(rest)? {
+                value @ 0..=15 => screen.fg = value as u8,
+                value @ 16..=31 => screen.set_attribute(screen.fg | ((value - 16) as u8) << 4),
+                _ => {}
+            },
+            _ => return None,
+        }
+        Some(3)
+    }
+
+    fn encode(current: &Cell, cell: &Cell) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        if cell.fg != current.fg {
+            bytes.extend(format!("|{:02}", cell.fg).bytes());
+        }
+        if cell.bg != current.bg || cell.blink != current.blink {
+            let blink = if cell.blink { 8 } else { 0 };
+            bytes.extend(format!("|{:02}", cell.bg + blink + 16).bytes());
+        }
+        bytes
+    }
+
+    fn escape(current: &Cell) -> Vec<u8> {
+        format!("|{:02}", current.fg).into_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::Canvas;
+    use crate::sauce::{DataType, FileType, Sauce};
+
+    fn codes(canvas: &Canvas, y: usize) -> Vec<u8> {
+        let row = &canvas.cells[y * canvas.width..(y + 1) * canvas.width];
+        row.iter().map(|cell| cell.code).collect()
+    }
+
+    #[test]
+    fn colors() {
+        let canvas = Pipe::from_bytes(b"|14A|17B|03C");
+        let a = canvas.get(0, 0).unwrap();
+        let b = canvas.get(1, 0).unwrap();
+        let c = canvas.get(2, 0).unwrap();
+        assert_eq!((a.code, a.fg, a.bg), (b'A', 14, 0));
+        assert_eq!((b.code, b.fg, b.bg), (b'B', 14, 1));
+        assert_eq!((c.code, c.fg, c.bg), (b'C', 3, 1));
+    }
+
+    #[test]
+    fn high_backgrounds_blink_or_use_ice_colors() {
+        let mut bytes = b"|25A|17B".to_vec();
+        let canvas = Pipe::from_bytes(&bytes);
+        let a = canvas.get(0, 0).unwrap();
+        let b = canvas.get(1, 0).unwrap();
+        assert_eq!((a.bg, a.blink), (1, true));
+        assert_eq!((b.bg, b.blink), (1, false));
+        let mut sauce = Sauce::new();
+        sauce.datatype = Some(DataType::Character);
+        sauce.filetype = Some(FileType::ANSI);
+        sauce.ice_colors = true;
+        sauce.add_to_bytes(&mut bytes).unwrap();
+        let canvas = Pipe::from_bytes(&bytes);
+        let a = canvas.get(0, 0).unwrap();
+        assert_eq!((a.bg, a.blink), (9, false));
+        let decoded = Pipe::from_bytes(&Pipe::to_bytes(&canvas).unwrap());
+        assert_eq!(decoded.get(0, 0).unwrap().bg, 9);
+    }
+
+    #[test]
+    fn cursor_and_line_codes() {
+        let canvas = Pipe::from_bytes(b"A|CRB|[X05C|[Y03D");
+        assert_eq!(canvas.get(0, 0).unwrap().code, b'A');
+        assert_eq!(canvas.get(0, 1).unwrap().code, b'B');
+        assert_eq!(canvas.get(4, 1).unwrap().code, b'C');
+        assert_eq!(canvas.get(5, 2).unwrap().code, b'D');
+    }
+
+    #[test]
+    fn unknown_codes_are_literal() {
+        let canvas = Pipe::from_bytes(b"|XY|1");
+        assert_eq!(&codes(&canvas, 0)[..5], b"|XY|1");
+    }
+
+    #[test]
+    fn literal_codes_round_trip() {
+        let text: &[u8] = b"a|12b|CRc|[X05d||07e|1";
+        let mut canvas = Canvas::new(132, 1);
+        for (x, code) in text.iter().enumerate() {
+            let fg = if x < 10 { 7 } else { 12 };
+            let cell = Cell {
+                code: *code,
+                fg,
+                ..Cell::default()
+            };
+            canvas.set(x, 0, cell);
+        }
+        let decoded = Pipe::from_bytes(&Pipe::to_bytes(&canvas).unwrap());
+        assert_eq!(decoded.width, 132);
+        assert_eq!(codes(&decoded, 0), codes(&canvas, 0));
+        for (a, b) in decoded.cells.iter().zip(canvas.cells.iter()) {
+            assert_eq!((a.fg, a.bg), (b.fg, b.bg));
+        }
+    }
+}
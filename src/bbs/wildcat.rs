@@ -0,0 +1,63 @@
+use crate::bbs::{hex_digit, MacroColor, Screen};
+use crate::canvas::Cell;
+
+pub struct Wildcat;
+
+impl MacroColor for Wildcat {
+    fn decode(screen: &mut Screen, bytes: &[u8]) -> Option<usize> {
+        match bytes {
+            [b'@', bg, fg, b'@', ..] => {
+                let attribute = (hex_digit(*bg)? << 4) | hex_digit(*fg)?;
+                screen.set_attribute(attribute);
+                Some(4)
+            }
+            _ => None,
+        }
+    }
+
+    fn encode(_current: &Cell, cell: &Cell) -> Vec<u8> {
+        let attribute = cell.attribute();
+        format!("@{:X}{:X}@", attribute >> 4, attribute & 15).into_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::Canvas;
+
+    fn codes(canvas: &Canvas) -> Vec<u8> {
+        canvas.cells.iter().map(|cell| cell.code).collect()
+    }
+
+    #[test]
+    fn colors() {
+        let canvas = Wildcat::from_bytes(b"@1F@A@4E@B");
+        let cells: Vec<(u8, u8, u8)> = canvas.cells[..2]
+            .iter()
+            .map(|cell| (cell.code, cell.fg, cell.bg))
+            .collect();
+        assert_eq!(cells, [(b'A', 15, 1), (b'B', 14, 4)]);
+    }
+
+    #[test]
+    fn literal_codes_round_trip() {
+        let text: &[u8] = b"a@1F@b@@07@c@0";
+        let mut canvas = Canvas::new(132, 1);
+        for (x, code) in text.iter().enumerate() {
+            let fg = if x < 6 { 7 } else { 11 };
+            let cell = Cell {
+                code: *code,
+                fg,
+                ..Cell::default()
+            };
+            canvas.set(x, 0, cell);
+        }
+        let decoded = Wildcat::from_bytes(&Wildcat::to_bytes(&canvas).unwrap());
+        assert_eq!(decoded.width, 132);
+        assert_eq!(codes(&decoded), codes(&canvas));
+        for (a, b) in decoded.cells.iter().zip(canvas.cells.iter()) {
+            assert_eq!((a.fg, a.bg), (b.fg, b.bg));
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use crate::bbs::{MacroColor, Screen};
+use crate::canvas::Cell;
+
+static HEART: u8 = 0x03;
+static WWIV_COLORS: [u8; 10] = [0x07, 0x0b, 0x0e, 0x05, 0x1f, 0x02, 0x8c, 0x09, 0x01, 0x03];
+
+pub struct Wwiv;
+
+impl MacroColor for Wwiv {
+    fn decode(screen: &mut Screen, bytes: &[u8]) -> Option<usize> {
+        match bytes {
+            [heart, digit @ b'0'..=b'9', ..] if *heart == HEART => {
+                screen.set_attribute(WWIV_COLORS[(digit - b'0') as usize]);
+                Some(2)
+            }
+            _ => None,
+        }
+    }
+
+    fn encode(_current: &Cell, cell: &Cell) -> Vec<u8> {
+        let attribute = cell.attribute();
+        let distance = |color: &u8| {
+            let fg = if color & 15 == attribute & 15 { 0 } else { 2 };
+            let bg = if color >> 4 == attribute >> 4 { 0 } else { 1 };
+            fg + bg
+        };
+        let (index, _) = WWIV_COLORS
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, color)| distance(color))
+            .unwrap();
+        vec![HEART, b'0' + index as u8]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::Canvas;
+
+    fn codes(canvas: &Canvas) -> Vec<u8> {
+        canvas.cells.iter().map(|cell| cell.code).collect()
+    }
+
+    #[test]
+    fn colors() {
+        let canvas = Wwiv::from_bytes(b"\x032A\x034B");
+        let cells: Vec<(u8, u8, u8)> = canvas.cells[..2]
+            .iter()
+            .map(|cell| (cell.code, cell.fg, cell.bg))
+            .collect();
+        assert_eq!(cells, [(b'A', 14, 0), (b'B', 15, 1)]);
+    }
+
+    #[test]
+    fn literal_codes_round_trip() {
+        let text: &[u8] = b"a\x031b\x03\x039c\x03";
+        let mut canvas = Canvas::new(132, 1);
+        for (x, code) in text.iter().enumerate() {
+            let fg = if x < 6 { 7 } else { 11 };
+            let cell = Cell {
+                code: *code,
+                fg,
+                ..Cell::default()
+            };
+            canvas.set(x, 0, cell);
+        }
+        let decoded = Wwiv::from_bytes(&Wwiv::to_bytes(&canvas).unwrap());
+        assert_eq!(decoded.width, 132);
+        assert_eq!(codes(&decoded), codes(&canvas));
+        for (a, b) in decoded.cells.iter().zip(canvas.cells.iter()) {
+            assert_eq!((a.fg, a.bg), (b.fg, b.bg));
+        }
+    }
+}
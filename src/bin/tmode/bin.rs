@@ -1,38 +1,72 @@
 mod font_opts;
+mod pack_opts;
+mod render_opts;
 mod sauce_opts;
+mod sheet_opts;
 use clap::{clap_app, crate_authors, crate_description, crate_version};
 use font_opts::font_opts;
+use pack_opts::pack_opts;
+use render_opts::render_opts;
 use sauce_opts::sauce_opts;
+use sheet_opts::sheet_opts;
 use std::error::Error;
 use std::process::exit;
-use std::u64;
 use wild::args;
 
+fn report(file: &str, e: &(dyn Error + 'static)) {
+    match e.downcast_ref::<tmode::Error>() {
+        Some(e) if e.path().is_some() => eprintln!("{}", e),
+        _ => eprintln!("{}: {}", file, e),
+    }
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
     let app = clap_app!(tmode =>
         (version: crate_version!())
         (author: crate_authors!())
         (about: crate_description!())
         (@arg current_date: --("current-date") requires("files") "Adds the current local date to SAUCE records.")
+        (@arg cursor: --cursor "Draws the text cursor in animated output.")
+        (@arg entries: --entries requires("files") "Reads SAUCE records from the entries of ZIP archives.")
         (@arg filetype: --filetype requires("files") "Automatically insert a SAUCE record for non-textmode files.")
+        (@arg html_font: --("html-font") requires("export_html") "Embeds the SAUCE font in HTML output as a sprite sheet.")
         (@arg ice_colors: --("ice-colors") requires("files") "Enables iCE colors for supported filetypes.")
         (@arg legacy_aspect: --("legacy-aspect") requires("files") "Enables legacy aspect ratio for supported filetypes.")
         (@arg letter_spacing: --("letter-spacing") requires("files") "Enables 9px fonts for supported filetypes.")
+        (@arg list_entries: --("list-entries") requires("files") "Lists the entries of ZIP archives.")
+        (@arg lint: --lint requires("files") "Checks SAUCE records for problems and reports them as JSON lines.")
         (@arg modern_aspect: --("modern-aspect") requires("files") "Enables modern aspect ratio for supported filetypes.")
         (@arg no_ice_colors: --("no-ice-colors") requires("files") "Disables iCE colors for supported filetypes.")
         (@arg no_letter_spacing: --("no-letter-spacing") requires("files") "Disables 9px fonts for supported filetypes.")
         (@arg no_sauce: --("no-sauce") requires("files") "Lists all the files with no SAUCE record.")
+        (@arg overwrite: --overwrite requires("filetype") "Allows --filetype to replace existing titles and comments.")
+        (@arg repair: --repair requires("files") "Rewrites damaged SAUCE records in canonical form.")
         (@arg sauce_display: -s --sauce requires("files") "Displays SAUCE information.")
         (@arg sauce_remove: -r --remove requires("files") "Removes SAUCE records.")
+        (@arg svg_symbols: --("svg-symbols") requires("export_svg") "Draws glyphs in SVG output as references to symbols.")
+        (@arg trim: --trim requires("files") "Trims trailing blank lines and columns when rendering.")
         (@arg unset_aspect: --("unset-aspect") requires("files") "Sets no preference on aspect ratio setting for supported filetypes.")
         (@arg unset_letter_spacing: --("unset-letter-spacing") requires("files") "Sets no preference on letter spacing for supported filetypes.")
+        (@arg view: --view requires("files") "Displays a file in the terminal.")
         (@arg author: --author +takes_value +require_equals +empty_values value_name("author's name") requires("files") "Adds an author to SAUCE records.")
         (@arg comments: --comments +takes_value +require_equals +empty_values requires("files") "Adds comments to SAUCE records.")
+        (@arg crop: --crop +takes_value +require_equals value_name("x,y,columns,rows") requires("files") "Crops a region of cells when rendering.")
         (@arg day: --day +takes_value +require_equals value_name("day of the month") requires("files") "Adds a day of the month to SAUCE records.")
+        (@arg export_apng: --("export-apng") +takes_value +require_equals value_name("PNG file") requires("files") "Renders an ANSI file with blinking text to an animated PNG file.")
         (@arg export_csv: --("export-csv") +takes_value +require_equals value_name("CSV file") requires("files") "Exports multiple SAUCE records to a CSV file.")
         (@arg export_font: --("export-font") +takes_value +require_equals value_name("font file") requires("files") "Extracts font data to a bitmask font file.")
         (@arg export_font_as_png: --("export-font-as-png") +takes_value +require_equals value_name("PNG file") requires("files") "Extracts font data to a PNG file.")
+        (@arg export_gif: --("export-gif") +takes_value +require_equals value_name("GIF file") requires("files") "Renders an ANSI file with blinking text to an animated GIF file.")
+        (@arg export_html: --("export-html") +takes_value +require_equals value_name("HTML file") requires("files") "Renders an ANSI file to an HTML file.")
         (@arg export_json: --("export-json") +takes_value +require_equals value_name("JSON file") requires("files") "Exports multiple SAUCE records to a JSON file.")
+        (@arg export_pcboard: --("export-pcboard") +takes_value +require_equals value_name("PCBoard file") requires("files") "Converts a file to PCBoard @X codes.")
+        (@arg export_pipe: --("export-pipe") +takes_value +require_equals value_name("pipe file") requires("files") "Converts a file to Renegade/Mystic pipe codes.")
+        (@arg export_png: --("export-png") +takes_value +require_equals value_name("PNG file") requires("files") "Renders an ANSI file to a PNG file.")
+        (@arg export_svg: --("export-svg") +takes_value +require_equals value_name("SVG file") requires("files") "Renders an ANSI file to an SVG file.")
+        (@arg export_thumbnail: --("export-thumbnail") +takes_value +require_equals value_name("PNG file") requires("files") "Renders an ANSI file to a thumbnail PNG file.")
+        (@arg export_wav: --("export-wav") +takes_value +require_equals value_name("WAV file") requires("files") "Synthesises ANSI music to a WAV file.")
+        (@arg export_wildcat: --("export-wildcat") +takes_value +require_equals value_name("Wildcat! file") requires("files") "Converts a file to Wildcat! @-codes.")
+        (@arg export_wwiv: --("export-wwiv") +takes_value +require_equals value_name("WWIV file") requires("files") "Converts a file to WWIV heart codes.")
         (@arg font: --font +takes_value +require_equals +empty_values value_name("font name") requires("files") "Adds a font name to SAUCE records.")
         (@arg font_as_png: --("font-as-png") +takes_value +require_equals value_name("PNG file") requires("files") "Converts a bitmask font file to a PNG file.\nUse with --chars-per-row. [default: 16]")
         (@arg font_height: --("font-height") +takes_value +require_equals "Specifies the font height when importing.")
@@ -41,22 +75,55 @@ fn main() -> Result<(), Box<dyn Error>> {
         (@arg group: --group +takes_value +require_equals +empty_values value_name("group's name") requires("files") "Adds a group to SAUCE records.")
         (@arg import_csv: --("import-csv") +takes_value +require_equals value_name("CSV file") "Imports a CSV file to update multiple SAUCE records.")
         (@arg import_json: --("import-json") +takes_value +require_equals value_name("JSON file") "Imports a JSON file to update multiple SAUCE records.")
+        (@arg input_format: --("input-format") +takes_value +require_equals possible_values(&["auto", "ansi", "atascii", "pcboard", "pipe", "wildcat", "wwiv"]) default_value("auto") "Specifies the format of the input file when rendering or converting.")
         (@arg info_1: --("info-1") +takes_value +require_equals value_name("numeric value") requires("files") "Sets a numeric value to information 1.")
         (@arg info_2: --("info-2") +takes_value +require_equals value_name("numeric value") requires("files") "Sets a numeric value to information 2.")
         (@arg info_3: --("info-3") +takes_value +require_equals value_name("numeric value") requires("files") "Sets a numeric value to information 3.")
         (@arg info_4: --("info-4") +takes_value +require_equals value_name("numeric value") requires("files") "Sets a numeric value to information 4.")
+        (@arg lines: --lines +takes_value +require_equals value_name("rows") requires("files") "Renders only the first rows of a file.")
         (@arg month: --month +takes_value +require_equals value_name("month of the year") requires("files") "Adds a (numeric) month of the year to SAUCE records.")
         (@arg png_as_font: --("png-as-font") +takes_value +require_equals value_name("font file") requires("files") "Converts a PNG file to a bitmask font file.\nUse with --font-width, --font-height, and --font-length.")
+        (@arg png_color: --("png-color") +takes_value +require_equals possible_values(&["auto", "rgb", "indexed", "rgba"]) default_value("rgb") "Specifies the colour type of rendered PNG files.")
+        (@arg split_rows: --("split-rows") +takes_value +require_equals value_name("rows") requires("export_png") "Splits PNG output into several files of this many text rows each.")
+        (@arg thumbnail_width: --("thumbnail-width") +takes_value +require_equals value_name("pixels") default_value("160") "Specifies the width of thumbnails.")
         (@arg title: --title +takes_value +require_equals +empty_values requires("files") "Adds a title to the SAUCE records.")
+        (@arg transparent: --transparent +takes_value +require_equals value_name("colour") default_value("0") "Specifies the colour made transparent by --png-color=rgba.")
+        (@arg view_colors: --("view-colors") +takes_value +require_equals possible_values(&["auto", "truecolor", "256", "16"]) default_value("auto") "Specifies the terminal colours used when viewing a file.")
+        (@arg view_mode: --("view-mode") +takes_value +require_equals possible_values(&["text", "half-block", "sixel", "kitty", "iterm2"]) default_value("text") "Specifies whether to view a file as text, as half-blocks, or as a Sixel, kitty or iTerm2 image.")
         (@arg year: --year +takes_value +require_equals requires("file") "Adds a year to SAUCE records.")
         (@arg chars_per_row: --("chars-per-row") +takes_value +require_equals default_value("16") +hidden)
         (@arg files: ... #{1, u64::MAX} value_name("FILE") "Sets the input file(s) to use.")
+        (@subcommand pack =>
+            (about: "Builds an artpack ZIP with a FILE_ID.DIZ, index and contact sheet.")
+            (@arg name: --name +takes_value +require_equals value_name("pack name") "Sets the pack name. [default: output file name]")
+            (@arg output: --output +takes_value +require_equals +required value_name("ZIP file") "Sets the ZIP file to write.")
+            (@arg files: ... +required value_name("FILE") "Sets the files to pack.")
+        )
+        (@subcommand sheet =>
+            (about: "Renders a contact sheet of thumbnails with SAUCE captions.")
+            (@arg output: --output +takes_value +require_equals +required value_name("PNG file") "Sets the PNG file to write.")
+            (@arg files: ... +required value_name("FILE") "Sets the files, directories or ZIP archives to include.")
+        )
     );
     let matches = if cfg!(windows) {
         app.get_matches_from(args())
     } else {
         app.get_matches()
     };
+    if let Some(matches) = matches.subcommand_matches("pack") {
+        if let Err(e) = pack_opts(matches) {
+            eprintln!("Error: {}", e);
+            exit(1);
+        }
+        return Ok(());
+    }
+    if let Some(matches) = matches.subcommand_matches("sheet") {
+        if let Err(e) = sheet_opts(matches) {
+            eprintln!("Error: {}", e);
+            exit(1);
+        }
+        return Ok(());
+    }
     if let Err(e) = sauce_opts(&matches) {
         eprintln!("Error: {}", e);
         exit(1);
@@ -65,5 +132,9 @@ fn main() -> Result<(), Box<dyn Error>> {
         eprintln!("Error: {}", e);
         exit(1);
     }
+    if let Err(e) = render_opts(&matches) {
+        eprintln!("Error: {}", e);
+        exit(1);
+    }
     Ok(())
 }
@@ -1,7 +1,9 @@
 use clap::ArgMatches;
 use std::error::Error;
 use std::path::Path;
+use tmode::detect::detect_file;
 use tmode::font::Font;
+use tmode::sauce::FileType;
 
 pub fn font_as_png(
     font_file: &str,
@@ -41,12 +43,16 @@ pub fn png_as_font(
     Ok(())
 }
 
-pub fn get_font_from_file(file: &str) -> Result<Font, Box<dyn Error>> {
+pub fn get_font_from_file(file: &str) -> tmode::Result<Font> {
+    if let Some(detection) = detect_file(file)? {
+        if detection.filetype == FileType::XBin {
+            return Font::from_xbin_file(file);
+        }
+    }
     let path = Path::new(file);
     match path.extension() {
         Some(extension) => match extension.to_str() {
             Some(extension) => match extension.to_ascii_uppercase().as_str() {
-                "XB" => Font::from_xbin_file(file),
                 "ADF" => Font::from_adf_file(file),
                 "IDF" => Font::from_idf_file(file),
                 _ => Font::from_sauce(file),
@@ -0,0 +1,25 @@
+use crate::report;
+use clap::ArgMatches;
+use std::error::Error;
+use std::path::Path;
+use tmode::pack::Pack;
+
+pub fn pack_opts(matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let output_file = matches.value_of("output").unwrap();
+    let name = match matches.value_of("name") {
+        Some(name) => name.to_string(),
+        None => Path::new(output_file).file_stem().map_or_else(
+            || output_file.to_string(),
+            |stem| stem.to_string_lossy().to_string(),
+        ),
+    };
+    let mut pack = Pack::new(&name);
+    for file in matches.values_of("files").unwrap() {
+        if let Err(e) = pack.add_file(file) {
+            report(file, &e);
+        }
+    }
+    pack.write_zip_file(output_file)?;
+    println!("{}: Packed {} files", output_file, pack.files.len());
+    Ok(())
+}
@@ -0,0 +1,484 @@
+use clap::ArgMatches;
+use std::error::Error;
+use std::io::{Cursor, Write};
+use tmode::ansi::{Ansi, Music};
+use tmode::archive::read_file_or_entry;
+use tmode::bbs::{MacroColor, PCBoard, Pipe, Wildcat, Wwiv};
+use tmode::canvas::Canvas;
+use tmode::detect::{detect, TextFormat};
+use tmode::font::Font;
+use tmode::render::{
+    canvas_from_bytes, canvas_to_terminal, image_to_iterm2, image_to_kitty, image_to_sixel,
+    image_to_terminal, Animation, BandRenderer, Html, Image, PngColor, Svg, SvgGlyphs,
+    TerminalColors, VGA_PALETTE,
+};
+use tmode::sauce::Sauce;
+
+static SAMPLE_RATE: usize = 22050;
+
+fn resolve_format(bytes: &[u8], file: &str, format: &str) -> TextFormat {
+    match format {
+        "ansi" => TextFormat::Ansi,
+        "atascii" => TextFormat::Atascii,
+        "pcboard" => TextFormat::PCBoard,
+        "pipe" => TextFormat::Pipe,
+        "wildcat" => TextFormat::Wildcat,
+        "wwiv" => TextFormat::Wwiv,
+        _ => detect(bytes, Some(file))
+            .and_then(|detection| detection.text_format)
+            .unwrap_or(TextFormat::Ansi),
+    }
+}
+
+pub struct Viewport {
+    crop: Option<(usize, usize, usize, usize)>,
+    lines: Option<usize>,
+    trim: bool,
+}
+
+impl Viewport {
+    pub fn from_matches(matches: &ArgMatches) -> Result<Viewport, Box<dyn Error>> {
+        let crop = match matches.value_of("crop") {
+            Some(value) => {
+                let values = value
+                    .split(',')
+                    .map(|value| value.trim().parse::<usize>())
+                    .collect::<Result<Vec<usize>, _>>()?;
+                match values.as_slice() {
+                    [x, y, columns, rows] => Some((*x, *y, *columns, *rows)),
+                    _ => return Err("Crop must be given as x,y,columns,rows".into()),
+                }
+            }
+            None => None,
+        };
+        let lines = match matches.value_of("lines") {
+            Some(value) => Some(value.parse()?),
+            None => None,
+        };
+        Ok(Viewport {
+            crop,
+            lines,
+            trim: matches.is_present("trim"),
+        })
+    }
+
+    fn apply(&self, mut canvas: Canvas) -> Result<Canvas, Box<dyn Error>> {
+        if let Some((x, y, columns, rows)) = self.crop {
+            canvas = canvas.crop(x, y, columns, rows);
+        }
+        if let Some(lines) = self.lines {
+            canvas = canvas.crop(0, 0, canvas.width, lines);
+        }
+        if self.trim {
+            canvas = canvas.trim();
+        }
+        if canvas.width == 0 || canvas.height == 0 {
+            return Err("Crop region is outside the canvas".into());
+        }
+        Ok(canvas)
+    }
+
+    fn cursor(&self, (x, y): (usize, usize)) -> Option<(usize, usize)> {
+        match self.crop {
+            Some((left, top, _, _)) => Some((x.checked_sub(left)?, y.checked_sub(top)?)),
+            None => Some((x, y)),
+        }
+    }
+}
+
+fn get_canvas_from_bytes(
+    bytes: &[u8],
+    format: TextFormat,
+    viewport: &Viewport,
+) -> Result<Canvas, Box<dyn Error>> {
+    viewport.apply(canvas_from_bytes(bytes, format))
+}
+
+pub fn get_canvas_from_file(
+    file: &str,
+    format: &str,
+    viewport: &Viewport,
+) -> Result<Canvas, Box<dyn Error>> {
+    let bytes = read_file_or_entry(file)?;
+    let format = resolve_format(&bytes, file, format);
+    get_canvas_from_bytes(&bytes, format, viewport)
+}
+
+fn get_font_from_bytes(bytes: &[u8], format: TextFormat) -> tmode::Result<Font> {
+    match format {
+        TextFormat::Atascii => "Atari ATASCII".parse::<Font>(),
+        _ => Font::from_sauce_reader(&mut Cursor::new(bytes)),
+    }
+}
+
+fn png_color(color: &str, transparent: &str) -> Result<PngColor, Box<dyn Error>> {
+    Ok(match color {
+        "indexed" => PngColor::Indexed,
+        "rgba" => match VGA_PALETTE.get(transparent.parse::<usize>()?) {
+            Some(rgb) => PngColor::Rgba(*rgb),
+            None => return Err("Transparent colour must be between 0 and 15".into()),
+        },
+        "auto" => PngColor::Auto,
+        _ => PngColor::Rgb,
+    })
+}
+
+pub fn export_png(
+    file: &str,
+    output_file: &str,
+    format: &str,
+    viewport: &Viewport,
+    split_rows: Option<usize>,
+    color: PngColor,
+) -> Result<(), Box<dyn Error>> {
+    let bytes = read_file_or_entry(file)?;
+    let format = resolve_format(&bytes, file, format);
+    let canvas = get_canvas_from_bytes(&bytes, format, viewport)?;
+    let font = get_font_from_bytes(&bytes, format)?;
+    let renderer = BandRenderer::new(&canvas, &font);
+    match split_rows {
+        Some(rows) => {
+            for part in renderer.as_split_pngs(output_file, rows, color)? {
+                println!("{}", part.display());
+            }
+        }
+        None => renderer.as_png(output_file, color)?,
+    }
+    Ok(())
+}
+
+pub fn export_thumbnail(
+    file: &str,
+    output_file: &str,
+    format: &str,
+    viewport: &Viewport,
+    width: usize,
+    color: PngColor,
+) -> Result<(), Box<dyn Error>> {
+    let bytes = read_file_or_entry(file)?;
+    let format = resolve_format(&bytes, file, format);
+    let canvas = get_canvas_from_bytes(&bytes, format, viewport)?;
+    let font = get_font_from_bytes(&bytes, format)?;
+    let image = Image::from_canvas(&canvas, &font);
+    image.thumbnail(width).as_png_with(output_file, color)?;
+    Ok(())
+}
+
+pub fn export_animation(
+    file: &str,
+    output_file: &str,
+    format: &str,
+    viewport: &Viewport,
+    cursor: bool,
+    gif: bool,
+) -> Result<(), Box<dyn Error>> {
+    let bytes = read_file_or_entry(file)?;
+    let format = resolve_format(&bytes, file, format);
+    let (canvas, position) = match format {
+        TextFormat::Ansi => {
+            let ansi = Ansi::from_bytes(&bytes);
+            let position = viewport.cursor(ansi.cursor());
+            (viewport.apply(ansi.canvas)?, position)
+        }
+        _ => (get_canvas_from_bytes(&bytes, format, viewport)?, None),
+    };
+    let font = get_font_from_bytes(&bytes, format)?;
+    let animation = Animation::from_canvas(&canvas, &font, position.filter(|_| cursor));
+    if gif {
+        animation.as_gif(output_file)?;
+    } else {
+        animation.as_apng(output_file)?;
+    }
+    Ok(())
+}
+
+pub fn export_html(
+    file: &str,
+    output_file: &str,
+    format: &str,
+    viewport: &Viewport,
+    embed_font: bool,
+) -> Result<(), Box<dyn Error>> {
+    let bytes = read_file_or_entry(file)?;
+    let format = resolve_format(&bytes, file, format);
+    let canvas = get_canvas_from_bytes(&bytes, format, viewport)?;
+    let font = get_font_from_bytes(&bytes, format)?;
+    let title = match Sauce::from_bytes(&bytes) {
+        Ok(Some(sauce)) if !sauce.title.is_empty() => sauce.title,
+        _ => file.to_string(),
+    };
+    let html = Html::from_canvas(&canvas, if embed_font { Some(&font) } else { None })?;
+    html.as_html_file(output_file, &title)?;
+    Ok(())
+}
+
+pub fn export_svg(
+    file: &str,
+    output_file: &str,
+    format: &str,
+    viewport: &Viewport,
+    glyphs: SvgGlyphs,
+) -> Result<(), Box<dyn Error>> {
+    let bytes = read_file_or_entry(file)?;
+    let format = resolve_format(&bytes, file, format);
+    let canvas = get_canvas_from_bytes(&bytes, format, viewport)?;
+    let font = get_font_from_bytes(&bytes, format)?;
+    let sauce = Sauce::from_bytes(&bytes).ok().flatten().unwrap_or_default();
+    let svg = Svg::from_canvas(
+        &canvas,
+        &font,
+        glyphs,
+        &sauce.letter_spacing.unwrap_or_default(),
+        &sauce.aspect_ratio.unwrap_or_default(),
+    );
+    svg.as_svg_file(output_file)?;
+    Ok(())
+}
+
+fn terminal_colors(colors: &str) -> TerminalColors {
+    match colors {
+        "truecolor" => TerminalColors::TrueColor,
+        "256" => TerminalColors::Palette256,
+        "16" => TerminalColors::Palette16,
+        _ => match std::env::var("COLORTERM") {
+            Ok(value) if value == "truecolor" || value == "24bit" => TerminalColors::TrueColor,
+            _ => match std::env::var("TERM") {
+                Ok(value) if value.contains("256") => TerminalColors::Palette256,
+                _ => TerminalColors::Palette16,
+            },
+        },
+    }
+}
+
+pub fn view(
+    file: &str,
+    mode: &str,
+    colors: &str,
+    format: &str,
+    viewport: &Viewport,
+) -> Result<(), Box<dyn Error>> {
+    let bytes = read_file_or_entry(file)?;
+    let format = resolve_format(&bytes, file, format);
+    let canvas = get_canvas_from_bytes(&bytes, format, viewport)?;
+    let colors = terminal_colors(colors);
+    let output = match mode {
+        "text" => canvas_to_terminal(&canvas, colors).into_bytes(),
+        mode => {
+            let font = get_font_from_bytes(&bytes, format)?;
+            let image = Image::from_canvas(&canvas, &font);
+            match mode {
+                "sixel" => image_to_sixel(&image),
+                "kitty" => image_to_kitty(&image)?,
+                "iterm2" => image_to_iterm2(&image)?,
+                _ => image_to_terminal(&image, colors).into_bytes(),
+            }
+        }
+    };
+    let mut stdout = std::io::stdout();
+    stdout.write_all(&output)?;
+    if mode != "text" && mode != "half-block" {
+        stdout.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+pub fn export_macro_color<T: MacroColor>(
+    file: &str,
+    output_file: &str,
+    format: &str,
+    viewport: &Viewport,
+) -> Result<(), Box<dyn Error>> {
+    let canvas = get_canvas_from_file(file, format, viewport)?;
+    T::to_file(&canvas, output_file)?;
+    Ok(())
+}
+
+pub fn export_wav(file: &str, output_file: &str) -> Result<(), Box<dyn Error>> {
+    let ansi = Ansi::from_bytes(&read_file_or_entry(file)?);
+    let music = Music {
+        notes: ansi
+            .music
+            .iter()
+            .flat_map(|music| music.notes.clone())
+            .collect(),
+        background: false,
+    };
+    if music.notes.is_empty() {
+        println!("{}: No ANSI music found", file);
+        return Ok(());
+    }
+    music.as_wav_file(output_file, SAMPLE_RATE)?;
+    println!(
+        "{}: Exported {} notes ({:.1}s) to {}",
+        file,
+        music.notes.len(),
+        music.duration(),
+        output_file
+    );
+    Ok(())
+}
+
+pub fn render_opts(matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let viewport = Viewport::from_matches(matches)?;
+    if matches.is_present("export_png") {
+        export_png(
+            matches.value_of("files").unwrap(),
+            matches.value_of("export_png").unwrap(),
+            matches.value_of("input_format").unwrap(),
+            &viewport,
+            match matches.value_of("split_rows") {
+                Some(rows) => Some(rows.parse()?),
+                None => None,
+            },
+            png_color(
+                matches.value_of("png_color").unwrap(),
+                matches.value_of("transparent").unwrap(),
+            )?,
+        )?;
+    }
+    if matches.is_present("export_thumbnail") {
+        export_thumbnail(
+            matches.value_of("files").unwrap(),
+            matches.value_of("export_thumbnail").unwrap(),
+            matches.value_of("input_format").unwrap(),
+            &viewport,
+            matches.value_of("thumbnail_width").unwrap().parse()?,
+            png_color(
+                matches.value_of("png_color").unwrap(),
+                matches.value_of("transparent").unwrap(),
+            )?,
+        )?;
+    }
+    if matches.is_present("export_apng") {
+        export_animation(
+            matches.value_of("files").unwrap(),
+            matches.value_of("export_apng").unwrap(),
+            matches.value_of("input_format").unwrap(),
+            &viewport,
+            matches.is_present("cursor"),
+            false,
+        )?;
+    }
+    if matches.is_present("export_gif") {
+        export_animation(
+            matches.value_of("files").unwrap(),
+            matches.value_of("export_gif").unwrap(),
+            matches.value_of("input_format").unwrap(),
+            &viewport,
+            matches.is_present("cursor"),
+            true,
+        )?;
+    }
+    if matches.is_present("export_html") {
+        export_html(
+            matches.value_of("files").unwrap(),
+            matches.value_of("export_html").unwrap(),
+            matches.value_of("input_format").unwrap(),
+            &viewport,
+            matches.is_present("html_font"),
+        )?;
+    }
+    if matches.is_present("export_svg") {
+        export_svg(
+            matches.value_of("files").unwrap(),
+            matches.value_of("export_svg").unwrap(),
+            matches.value_of("input_format").unwrap(),
+            &viewport,
+            if matches.is_present("svg_symbols") {
+                SvgGlyphs::Symbols
+            } else {
+                SvgGlyphs::Paths
+            },
+        )?;
+    }
+    if matches.is_present("view") {
+        for file in matches.values_of("files").unwrap() {
+            view(
+                file,
+                matches.value_of("view_mode").unwrap(),
+                matches.value_of("view_colors").unwrap(),
+                matches.value_of("input_format").unwrap(),
+                &viewport,
+            )?;
+        }
+    }
+    if matches.is_present("export_pcboard") {
+        export_macro_color::<PCBoard>(
+            matches.value_of("files").unwrap(),
+            matches.value_of("export_pcboard").unwrap(),
+            matches.value_of("input_format").unwrap(),
+            &viewport,
+        )?;
+    }
+    if matches.is_present("export_pipe") {
+        export_macro_color::<Pipe>(
+            matches.value_of("files").unwrap(),
+            matches.value_of("export_pipe").unwrap(),
+            matches.value_of("input_format").unwrap(),
+            &viewport,
+        )?;
+    }
+    if matches.is_present("export_wildcat") {
+        export_macro_color::<Wildcat>(
+            matches.value_of("files").unwrap(),
+            matches.value_of("export_wildcat").unwrap(),
+            matches.value_of("input_format").unwrap(),
+            &viewport,
+        )?;
+    }
+    if matches.is_present("export_wwiv") {
+        export_macro_color::<Wwiv>(
+            matches.value_of("files").unwrap(),
+            matches.value_of("export_wwiv").unwrap(),
+            matches.value_of("input_format").unwrap(),
+            &viewport,
+        )?;
+    }
+    if matches.is_present("export_wav") {
+        export_wav(
+            matches.value_of("files").unwrap(),
+            matches.value_of("export_wav").unwrap(),
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn viewport(crop: Option<(usize, usize, usize, usize)>, lines: Option<usize>) -> Viewport {
+        Viewport {
+            crop,
+            lines,
+            trim: false,
+        }
+    }
+
+    #[test]
+    fn crop_outside_the_canvas_is_an_error() {
+        let directory = std::env::temp_dir().join(format!("tmode-crop-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let file = directory.join("t.ans");
+        std::fs::write(&file, b"\x1b[1;31mcrop\r\nme").unwrap();
+        let file = file.to_str().unwrap();
+        let output = directory.join("t.html");
+        let output = output.to_str().unwrap();
+        let outside = viewport(Some((100, 0, 10, 10)), None);
+        for result in [
+            view(file, "text", "16", "auto", &outside),
+            export_html(file, output, "auto", &outside, false),
+            export_png(file, output, "auto", &outside, None, PngColor::Rgb),
+            export_html(file, output, "auto", &viewport(None, Some(0)), false),
+        ]
+        .iter()
+        {
+            let error = result.as_ref().unwrap_err();
+            assert_eq!(error.to_string(), "Crop region is outside the canvas");
+        }
+        let inside = viewport(Some((2, 1, 10, 10)), None);
+        let canvas = get_canvas_from_file(file, "auto", &inside).unwrap();
+        assert_eq!((canvas.width, canvas.height), (10, 1));
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -1,174 +1,86 @@
+use crate::report;
 use chrono::{Datelike, Local};
 use clap::{ArgMatches, Values};
 use fs::File;
-use image::image_dimensions;
+use serde_json::Value;
 use std::error::Error;
 use std::fs;
-use std::path::Path;
+use tmode::archive::{is_archive, Archive};
+use tmode::detect::detect_file;
 use tmode::sauce::{AspectRatio, DataType, FileType, LetterSpacing, Sauce};
+use tmode::tracker::Module;
+
+fn read_sauces(values: Values, entries: bool) -> Vec<(String, tmode::Result<Option<Sauce>>)> {
+    let mut sauces = Vec::new();
+    for file in values {
+        if entries && is_archive(file) {
+            match Archive::from_file(file).and_then(|mut archive| archive.sauces()) {
+                Ok(mut archive_sauces) => sauces.append(&mut archive_sauces),
+                Err(e) => sauces.push((file.to_string(), Err(e))),
+            }
+        } else {
+            sauces.push((file.to_string(), Sauce::from_file(file)));
+        }
+    }
+    sauces
+}
 
 fn sauce_remove(values: Values) -> Result<(), Box<dyn Error>> {
     for file in values {
         match Sauce::remove_from_file(file) {
             Ok(()) => println!("{}: SAUCE record removed", file),
-            Err(e) => eprintln!("{}: {}", file, e),
+            Err(e) => report(file, &e),
         }
     }
     Ok(())
 }
 
-fn filetype(values: Values) -> Result<(), Box<dyn Error>> {
+fn filetype(values: Values, overwrite: bool) -> Result<(), Box<dyn Error>> {
     for file in values {
-        let path = Path::new(file);
-        if let Some(extension) = path.extension() {
-            if let Some(extension) = extension.to_str() {
-                match extension.to_uppercase().as_str() {
-                    "GIF" => match image_dimensions(file) {
-                        Ok((width, height)) => match Sauce::from_file(file) {
-                            Ok(sauce) => {
-                                let mut sauce = match sauce {
-                                    Some(sauce) => sauce,
-                                    None => Sauce::new(),
-                                };
-                                sauce.datatype = Some(DataType::Bitmap);
-                                sauce.filetype = Some(FileType::GIF);
-                                sauce.info_1 = width as usize;
-                                sauce.info_2 = height as usize;
-                                match sauce.add_to_file(file) {
-                                    Ok(_) => println!(
-                                        "{}: Added filetype Bitmap/GIF ({}x{})",
-                                        file, width, height
-                                    ),
-                                    Err(e) => eprintln!("{}: {}", file, e),
-                                }
-                            }
-                            Err(e) => eprintln!("{}: {}", file, e),
-                        },
-                        Err(e) => eprintln!("{}: {}", file, e),
-                    },
-                    "IT" => match Sauce::from_file(file) {
-                        Ok(sauce) => {
-                            let mut sauce = match sauce {
-                                Some(sauce) => sauce,
-                                None => Sauce::new(),
-                            };
-                            sauce.datatype = Some(DataType::Audio);
-                            sauce.filetype = Some(FileType::IT);
-                            match sauce.add_to_file(file) {
-                                Ok(_) => println!("{}: Added filetype Audio/IT", file),
-                                Err(e) => eprintln!("{}: {}", file, e),
-                            }
-                        }
-                        Err(e) => eprintln!("{}: {}", file, e),
-                    },
-                    "JPG" | "JPEG " => match image_dimensions(file) {
-                        Ok((width, height)) => match Sauce::from_file(file) {
-                            Ok(sauce) => {
-                                let mut sauce = match sauce {
-                                    Some(sauce) => sauce,
-                                    None => Sauce::new(),
-                                };
-                                sauce.datatype = Some(DataType::Bitmap);
-                                sauce.filetype = Some(FileType::JPG);
-                                sauce.info_1 = width as usize;
-                                sauce.info_2 = height as usize;
-                                match sauce.add_to_file(file) {
-                                    Ok(_) => println!(
-                                        "{}: Added filetype Bitmap/JPG ({}x{})",
-                                        file, width, height
-                                    ),
-                                    Err(e) => eprintln!("{}: {}", file, e),
-                                }
-                            }
-                            Err(e) => eprintln!("{}: {}", file, e),
-                        },
-                        Err(_) => match Sauce::from_file(file) {
-                            Ok(sauce) => {
-                                let mut sauce = match sauce {
-                                    Some(sauce) => sauce,
-                                    None => Sauce::new(),
-                                };
-                                sauce.datatype = Some(DataType::Bitmap);
-                                sauce.filetype = Some(FileType::JPG);
-                                match sauce.add_to_file(file) {
-                                    Ok(_) => println!("{}: Added filetype Bitmap/JPG", file),
-                                    Err(e) => eprintln!("{}: {}", file, e),
-                                }
-                            }
-                            Err(e) => eprintln!("{}: {}", file, e),
-                        },
-                    },
-                    "MP4" => match Sauce::from_file(file) {
-                        Ok(sauce) => {
-                            let mut sauce = match sauce {
-                                Some(sauce) => sauce,
-                                None => Sauce::new(),
-                            };
-                            sauce.datatype = Some(DataType::Bitmap);
-                            sauce.filetype = Some(FileType::MPG);
-                            match sauce.add_to_file(file) {
-                                Ok(_) => println!("{}: Added filetype Bitmap/MPG", file),
-                                Err(e) => eprintln!("{}: {}", file, e),
-                            }
-                        }
-                        Err(e) => eprintln!("{}: {}", file, e),
-                    },
-                    "PNG" => match image_dimensions(file) {
-                        Ok((width, height)) => match Sauce::from_file(file) {
-                            Ok(sauce) => {
-                                let mut sauce = match sauce {
-                                    Some(sauce) => sauce,
-                                    None => Sauce::new(),
-                                };
-                                sauce.datatype = Some(DataType::Bitmap);
-                                sauce.filetype = Some(FileType::PNG);
-                                sauce.info_1 = width as usize;
-                                sauce.info_2 = height as usize;
-                                match sauce.add_to_file(file) {
-                                    Ok(_) => println!(
-                                        "{}: Added filetype Bitmap/PNG ({}x{})",
-                                        file, width, height
-                                    ),
-                                    Err(e) => eprintln!("{}: {}", file, e),
-                                }
-                            }
-                            Err(e) => eprintln!("{}: {}", file, e),
-                        },
-                        Err(e) => eprintln!("{}: {}", file, e),
-                    },
-                    "RIP" => match Sauce::from_file(file) {
-                        Ok(sauce) => {
-                            let mut sauce = match sauce {
-                                Some(sauce) => sauce,
-                                None => Sauce::new(),
-                            };
-                            sauce.datatype = Some(DataType::Character);
-                            sauce.filetype = Some(FileType::RIPScript);
-                            match sauce.add_to_file(file) {
-                                Ok(_) => println!("{}: Added filetype Character/RIPScript", file),
-                                Err(e) => eprintln!("{}: {}", file, e),
-                            }
-                        }
-                        Err(e) => eprintln!("{}: {}", file, e),
-                    },
-                    "S3M" => match Sauce::from_file(file) {
-                        Ok(sauce) => {
-                            let mut sauce = match sauce {
-                                Some(sauce) => sauce,
-                                None => Sauce::new(),
-                            };
-                            sauce.datatype = Some(DataType::Audio);
-                            sauce.filetype = Some(FileType::S3M);
-                            match sauce.add_to_file(file) {
-                                Ok(_) => println!("{}: Added filetype Audio/S3M", file),
-                                Err(e) => eprintln!("{}: {}", file, e),
-                            }
-                        }
-                        Err(e) => eprintln!("{}: {}", file, e),
+        let detection = match detect_file(file) {
+            Ok(Some(detection)) => detection,
+            Ok(None) => continue,
+            Err(e) => {
+                report(file, &e);
+                continue;
+            }
+        };
+        match (detection.datatype, detection.filetype) {
+            (DataType::Character, FileType::RIPScript) => {}
+            (DataType::Character, _) | (DataType::BinaryText, _) | (DataType::XBin, _) => continue,
+            _ => {}
+        }
+        match Sauce::from_file(file) {
+            Ok(sauce) => {
+                let mut sauce = match sauce {
+                    Some(sauce) => sauce,
+                    None => Sauce::new(),
+                };
+                detection.apply_to_sauce(&mut sauce);
+                match Module::from_file(file, detection.filetype) {
+                    Ok(Some(module)) => module.apply_to_sauce(&mut sauce, overwrite),
+                    Ok(None) => {}
+                    Err(e) => report(file, &e),
+                }
+                match sauce.add_to_file(file) {
+                    Ok(_) => match detection.datatype {
+                        DataType::Bitmap if detection.info_1 > 0 => println!(
+                            "{}: Added filetype {}/{} ({}x{})",
+                            file,
+                            detection.datatype,
+                            detection.filetype,
+                            detection.info_1,
+                            detection.info_2
+                        ),
+                        _ => println!(
+                            "{}: Added filetype {}/{}",
+                            file, detection.datatype, detection.filetype
+                        ),
                     },
-                    _ => {}
+                    Err(e) => report(file, &e),
                 }
             }
+            Err(e) => report(file, &e),
         }
     }
     Ok(())
@@ -182,11 +94,11 @@ fn title(values: Values, title: &str) -> Result<(), Box<dyn Error>> {
                     sauce.title = title.to_string();
                     match sauce.add_to_file(file) {
                         Ok(_) => println!("{}: Added title", file),
-                        Err(e) => eprintln!("{}: {}", file, e),
+                        Err(e) => report(file, &e),
                     }
                 }
             }
-            Err(e) => eprintln!("{}: {}", file, e),
+            Err(e) => report(file, &e),
         }
     }
     Ok(())
@@ -200,11 +112,11 @@ fn author(values: Values, author: &str) -> Result<(), Box<dyn Error>> {
                     sauce.author = author.to_string();
                     match sauce.add_to_file(file) {
                         Ok(_) => println!("{}: Added author", file),
-                        Err(e) => eprintln!("{}: {}", file, e),
+                        Err(e) => report(file, &e),
                     }
                 }
             }
-            Err(e) => eprintln!("{}: {}", file, e),
+            Err(e) => report(file, &e),
         }
     }
     Ok(())
@@ -218,11 +130,11 @@ fn group(values: Values, group: &str) -> Result<(), Box<dyn Error>> {
                     sauce.group = group.to_string();
                     match sauce.add_to_file(file) {
                         Ok(_) => println!("{}: Added group", file),
-                        Err(e) => eprintln!("{}: {}", file, e),
+                        Err(e) => report(file, &e),
                     }
                 }
             }
-            Err(e) => eprintln!("{}: {}", file, e),
+            Err(e) => report(file, &e),
         }
     }
     Ok(())
@@ -242,11 +154,11 @@ fn current_date(values: Values) -> Result<(), Box<dyn Error>> {
                     sauce.date = format!("{:02}", day);
                     match sauce.add_to_file(file) {
                         Ok(_) => println!("{}: Added current date", file),
-                        Err(e) => eprintln!("{}: {}", file, e),
+                        Err(e) => report(file, &e),
                     }
                 }
             }
-            Err(e) => eprintln!("{}: {}", file, e),
+            Err(e) => report(file, &e),
         }
     }
     Ok(())
@@ -262,14 +174,14 @@ fn year(values: Values, year: &str) -> Result<(), Box<dyn Error>> {
                             sauce.year = format!("{:04}", year);
                             match sauce.add_to_file(file) {
                                 Ok(_) => println!("{}: Added year", file),
-                                Err(e) => eprintln!("{}: {}", file, e),
+                                Err(e) => report(file, &e),
                             }
                         }
-                        Err(e) => eprintln!("{}: {}", file, e),
+                        Err(e) => report(file, &e),
                     }
                 }
             }
-            Err(e) => eprintln!("{}: {}", file, e),
+            Err(e) => report(file, &e),
         }
     }
     Ok(())
@@ -285,14 +197,14 @@ fn month(values: Values, month: &str) -> Result<(), Box<dyn Error>> {
                             sauce.month = format!("{:02}", month);
                             match sauce.add_to_file(file) {
                                 Ok(_) => println!("{}: Added month", file),
-                                Err(e) => eprintln!("{}: {}", file, e),
+                                Err(e) => report(file, &e),
                             }
                         }
-                        Err(e) => eprintln!("{}: {}", file, e),
+                        Err(e) => report(file, &e),
                     }
                 }
             }
-            Err(e) => eprintln!("{}: {}", file, e),
+            Err(e) => report(file, &e),
         }
     }
     Ok(())
@@ -308,14 +220,14 @@ fn day(values: Values, day: &str) -> Result<(), Box<dyn Error>> {
                             sauce.date = format!("{:02}", day);
                             match sauce.add_to_file(file) {
                                 Ok(_) => println!("{}: Added day", file),
-                                Err(e) => eprintln!("{}: {}", file, e),
+                                Err(e) => report(file, &e),
                             }
                         }
-                        Err(e) => eprintln!("{}: {}", file, e),
+                        Err(e) => report(file, &e),
                     }
                 }
             }
-            Err(e) => eprintln!("{}: {}", file, e),
+            Err(e) => report(file, &e),
         }
     }
     Ok(())
@@ -326,19 +238,24 @@ fn info_1(values: Values, info_1: &str) -> Result<(), Box<dyn Error>> {
         match Sauce::from_file(file) {
             Ok(sauce) => {
                 if let Some(mut sauce) = sauce {
-                    match info_1.parse::<usize>() {
+                    match info_1.parse::<u16>().map(usize::from) {
                         Ok(info_1) => {
                             sauce.info_1 = info_1;
                             match sauce.add_to_file(file) {
-                                Ok(_) => println!("{}: Added value", file),
-                                Err(e) => eprintln!("{}: {}", file, e),
+                                Ok(_) => match sauce.info_fields()[0] {
+                                    Some(field) => {
+                                        println!("{}: Changed {} to {}", file, field, info_1)
+                                    }
+                                    None => println!("{}: Added value", file),
+                                },
+                                Err(e) => report(file, &e),
                             }
                         }
-                        Err(e) => eprintln!("{}: {}", file, e),
+                        Err(e) => report(file, &e),
                     }
                 }
             }
-            Err(e) => eprintln!("{}: {}", file, e),
+            Err(e) => report(file, &e),
         }
     }
     Ok(())
@@ -349,19 +266,24 @@ fn info_2(values: Values, info_2: &str) -> Result<(), Box<dyn Error>> {
         match Sauce::from_file(file) {
             Ok(sauce) => {
                 if let Some(mut sauce) = sauce {
-                    match info_2.parse::<usize>() {
+                    match info_2.parse::<u16>().map(usize::from) {
                         Ok(info_2) => {
                             sauce.info_2 = info_2;
                             match sauce.add_to_file(file) {
-                                Ok(_) => println!("{}: Added value", file),
-                                Err(e) => eprintln!("{}: {}", file, e),
+                                Ok(_) => match sauce.info_fields()[1] {
+                                    Some(field) => {
+                                        println!("{}: Changed {} to {}", file, field, info_2)
+                                    }
+                                    None => println!("{}: Added value", file),
+                                },
+                                Err(e) => report(file, &e),
                             }
                         }
-                        Err(e) => eprintln!("{}: {}", file, e),
+                        Err(e) => report(file, &e),
                     }
                 }
             }
-            Err(e) => eprintln!("{}: {}", file, e),
+            Err(e) => report(file, &e),
         }
     }
     Ok(())
@@ -372,19 +294,24 @@ fn info_3(values: Values, info_3: &str) -> Result<(), Box<dyn Error>> {
         match Sauce::from_file(file) {
             Ok(sauce) => {
                 if let Some(mut sauce) = sauce {
-                    match info_3.parse::<usize>() {
+                    match info_3.parse::<u16>().map(usize::from) {
                         Ok(info_3) => {
                             sauce.info_3 = info_3;
                             match sauce.add_to_file(file) {
-                                Ok(_) => println!("{}: Added value", file),
-                                Err(e) => eprintln!("{}: {}", file, e),
+                                Ok(_) => match sauce.info_fields()[2] {
+                                    Some(field) => {
+                                        println!("{}: Changed {} to {}", file, field, info_3)
+                                    }
+                                    None => println!("{}: Added value", file),
+                                },
+                                Err(e) => report(file, &e),
                             }
                         }
-                        Err(e) => eprintln!("{}: {}", file, e),
+                        Err(e) => report(file, &e),
                     }
                 }
             }
-            Err(e) => eprintln!("{}: {}", file, e),
+            Err(e) => report(file, &e),
         }
     }
     Ok(())
@@ -395,54 +322,52 @@ fn info_4(values: Values, info_4: &str) -> Result<(), Box<dyn Error>> {
         match Sauce::from_file(file) {
             Ok(sauce) => {
                 if let Some(mut sauce) = sauce {
-                    match info_4.parse::<usize>() {
+                    match info_4.parse::<u16>().map(usize::from) {
                         Ok(info_4) => {
                             sauce.info_4 = info_4;
                             match sauce.add_to_file(file) {
-                                Ok(_) => println!("{}: Added value", file),
-                                Err(e) => eprintln!("{}: {}", file, e),
+                                Ok(_) => match sauce.info_fields()[3] {
+                                    Some(field) => {
+                                        println!("{}: Changed {} to {}", file, field, info_4)
+                                    }
+                                    None => println!("{}: Added value", file),
+                                },
+                                Err(e) => report(file, &e),
                             }
                         }
-                        Err(e) => eprintln!("{}: {}", file, e),
+                        Err(e) => report(file, &e),
                     }
                 }
             }
-            Err(e) => eprintln!("{}: {}", file, e),
+            Err(e) => report(file, &e),
         }
     }
     Ok(())
 }
 
+fn supports_flags(file: &str) -> bool {
+    match detect_file(file) {
+        Ok(detection) => detection.map_or(false, |detection| detection.supports_flags()),
+        Err(e) => {
+            report(file, &e);
+            false
+        }
+    }
+}
+
 fn set_ice_colors(values: Values, ice_colors: bool) -> Result<(), Box<dyn Error>> {
-    for file in values {
+    for file in values.filter(|file| supports_flags(file)) {
         match Sauce::from_file(file) {
             Ok(sauce) => {
                 if let Some(mut sauce) = sauce {
-                    if let Some(DataType::BinaryText) = sauce.datatype {
-                        sauce.ice_colors = ice_colors;
-                        match sauce.add_to_file(file) {
-                            Ok(_) => println!("{}: Changed ice-colors to {}", file, ice_colors),
-                            Err(e) => eprintln!("{}: {}", file, e),
-                        }
-                    } else {
-                        match sauce.filetype {
-                            Some(FileType::ASCII)
-                            | Some(FileType::ANSI)
-                            | Some(FileType::ANSImation) => {
-                                sauce.ice_colors = ice_colors;
-                                match sauce.add_to_file(file) {
-                                    Ok(_) => {
-                                        println!("{}: Changed ice-colors to {}", file, ice_colors)
-                                    }
-                                    Err(e) => eprintln!("{}: {}", file, e),
-                                }
-                            }
-                            _ => {}
-                        }
+                    sauce.ice_colors = ice_colors;
+                    match sauce.add_to_file(file) {
+                        Ok(_) => println!("{}: Changed ice-colors to {}", file, ice_colors),
+                        Err(e) => report(file, &e),
                     }
                 }
             }
-            Err(e) => eprintln!("{}: {}", file, e),
+            Err(e) => report(file, &e),
         }
     }
     Ok(())
@@ -452,48 +377,24 @@ fn set_aspect_ratio(
     values: Values,
     aspect_ratio: Option<AspectRatio>,
 ) -> Result<(), Box<dyn Error>> {
-    for file in values {
+    for file in values.filter(|file| supports_flags(file)) {
         match Sauce::from_file(file) {
             Ok(sauce) => {
                 if let Some(mut sauce) = sauce {
-                    if let Some(DataType::BinaryText) = sauce.datatype {
-                        sauce.aspect_ratio = aspect_ratio.clone();
-                        match sauce.add_to_file(file) {
-                            Ok(_) => {
-                                if let Some(aspect_ratio) = sauce.aspect_ratio {
-                                    println!("{}: Changed aspect ratio to {}", file, aspect_ratio);
-                                } else {
-                                    println!("{}: Removed aspect ratio setting", file);
-                                }
-                            }
-                            Err(e) => eprintln!("{}: {}", file, e),
-                        }
-                    } else {
-                        match sauce.filetype {
-                            Some(FileType::ASCII)
-                            | Some(FileType::ANSI)
-                            | Some(FileType::ANSImation) => {
-                                sauce.aspect_ratio = aspect_ratio.clone();
-                                match sauce.add_to_file(file) {
-                                    Ok(_) => {
-                                        if let Some(aspect_ratio) = sauce.aspect_ratio {
-                                            println!(
-                                                "{}: Changed aspect ratio to {}",
-                                                file, aspect_ratio
-                                            );
-                                        } else {
-                                            println!("{}: Removed aspect ratio setting", file);
-                                        }
-                                    }
-                                    Err(e) => eprintln!("{}: {}", file, e),
-                                }
+                    sauce.aspect_ratio = aspect_ratio.clone();
+                    match sauce.add_to_file(file) {
+                        Ok(_) => {
+                            if let Some(aspect_ratio) = sauce.aspect_ratio {
+                                println!("{}: Changed aspect ratio to {}", file, aspect_ratio);
+                            } else {
+                                println!("{}: Removed aspect ratio setting", file);
                             }
-                            _ => {}
                         }
+                        Err(e) => report(file, &e),
                     }
                 }
             }
-            Err(e) => eprintln!("{}: {}", file, e),
+            Err(e) => report(file, &e),
         }
     }
     Ok(())
@@ -503,51 +404,24 @@ fn set_letter_spacing(
     values: Values,
     letter_spacing: Option<LetterSpacing>,
 ) -> Result<(), Box<dyn Error>> {
-    for file in values {
+    for file in values.filter(|file| supports_flags(file)) {
         match Sauce::from_file(file) {
             Ok(sauce) => {
                 if let Some(mut sauce) = sauce {
-                    if let Some(DataType::BinaryText) = sauce.datatype {
-                        sauce.letter_spacing = letter_spacing.clone();
-                        match sauce.add_to_file(file) {
-                            Ok(_) => {
-                                if let Some(letter_spacing) = sauce.letter_spacing {
-                                    println!(
-                                        "{}: Changed letter spacing to {}",
-                                        file, letter_spacing
-                                    );
-                                } else {
-                                    println!("{}: Removed letter spacing setting", file);
-                                }
-                            }
-                            Err(e) => eprintln!("{}: {}", file, e),
-                        }
-                    } else {
-                        match sauce.filetype {
-                            Some(FileType::ASCII)
-                            | Some(FileType::ANSI)
-                            | Some(FileType::ANSImation) => {
-                                sauce.letter_spacing = letter_spacing.clone();
-                                match sauce.add_to_file(file) {
-                                    Ok(_) => {
-                                        if let Some(letter_spacing) = sauce.letter_spacing {
-                                            println!(
-                                                "{}: Changed letter spacing to {}",
-                                                file, letter_spacing
-                                            );
-                                        } else {
-                                            println!("{}: Removed letter spacing setting", file);
-                                        }
-                                    }
-                                    Err(e) => eprintln!("{}: {}", file, e),
-                                }
+                    sauce.letter_spacing = letter_spacing.clone();
+                    match sauce.add_to_file(file) {
+                        Ok(_) => {
+                            if let Some(letter_spacing) = sauce.letter_spacing {
+                                println!("{}: Changed letter spacing to {}", file, letter_spacing);
+                            } else {
+                                println!("{}: Removed letter spacing setting", file);
                             }
-                            _ => {}
                         }
+                        Err(e) => report(file, &e),
                     }
                 }
             }
-            Err(e) => eprintln!("{}: {}", file, e),
+            Err(e) => report(file, &e),
         }
     }
     Ok(())
@@ -561,11 +435,11 @@ fn font(values: Values, font: &str) -> Result<(), Box<dyn Error>> {
                     sauce.font_name = font.to_string();
                     match sauce.add_to_file(file) {
                         Ok(_) => println!("{}: Added font", file),
-                        Err(e) => eprintln!("{}: {}", file, e),
+                        Err(e) => report(file, &e),
                     }
                 }
             }
-            Err(e) => eprintln!("{}: {}", file, e),
+            Err(e) => report(file, &e),
         }
     }
     Ok(())
@@ -583,70 +457,109 @@ fn comments(values: Values, comments: &str) -> Result<(), Box<dyn Error>> {
                     };
                     match sauce.add_to_file(file) {
                         Ok(_) => println!("{}: Added comments", file),
-                        Err(e) => eprintln!("{}: {}", file, e),
+                        Err(e) => report(file, &e),
                     }
                 }
             }
-            Err(e) => eprintln!("{}: {}", file, e),
+            Err(e) => report(file, &e),
         }
     }
     Ok(())
 }
 
-fn no_sauce(values: Values) -> Result<(), Box<dyn Error>> {
-    for file in values {
-        match Sauce::from_file(file) {
+fn no_sauce(values: Values, entries: bool) -> Result<(), Box<dyn Error>> {
+    for (file, sauce) in read_sauces(values, entries) {
+        match sauce {
             Ok(sauce) => {
                 if sauce.is_none() {
                     println!("{}: No SAUCE record found", file);
                 }
             }
-            Err(e) => eprintln!("{}: {}", file, e),
+            Err(e) => report(&file, &e),
         }
     }
     Ok(())
 }
 
-fn sauce_display(values: Values) -> Result<(), Box<dyn Error>> {
-    for file in values {
-        match Sauce::from_file(file) {
+fn sauce_display(values: Values, entries: bool) -> Result<(), Box<dyn Error>> {
+    for (file, sauce) in read_sauces(values, entries) {
+        match sauce {
             Ok(sauce) => {
                 if let Some(sauce) = sauce {
                     println!("{}", sauce);
                 }
             }
-            Err(e) => eprintln!("{}: {}", file, e),
+            Err(e) => report(&file, &e),
         }
     }
     Ok(())
 }
 
-fn export_csv(values: Values, csv_file: &str) -> Result<(), Box<dyn Error>> {
-    let mut wtr = csv::Writer::from_path(csv_file)?;
+fn repair(values: Values) -> Result<(), Box<dyn Error>> {
     for file in values {
-        match Sauce::from_file(file) {
+        match Sauce::repair_file(file) {
+            Ok(Some(warnings)) => {
+                for warning in warnings {
+                    println!("{}: {}", file, warning);
+                }
+                println!("{}: SAUCE record repaired", file);
+            }
+            Ok(None) => {}
+            Err(e) => report(file, &e),
+        }
+    }
+    Ok(())
+}
+
+fn lint(values: Values, entries: bool) -> Result<(), Box<dyn Error>> {
+    let mut count = 0;
+    for (file, sauce) in read_sauces(values, entries) {
+        match sauce {
+            Ok(Some(sauce)) => {
+                for problem in sauce.validate() {
+                    let mut value = serde_json::to_value(&problem)?;
+                    value["file"] = Value::from(file.as_str());
+                    value["message"] = Value::from(problem.to_string());
+                    println!("{}", value);
+                    count += 1;
+                }
+            }
+            Ok(None) => {}
+            Err(e) => report(&file, &e),
+        }
+    }
+    if count > 0 {
+        return Err(format!("{} problem(s) found", count).into());
+    }
+    Ok(())
+}
+
+fn export_csv(values: Values, entries: bool, csv_file: &str) -> Result<(), Box<dyn Error>> {
+    let mut wtr = csv::Writer::from_path(csv_file)?;
+    for (file, sauce) in read_sauces(values, entries) {
+        match sauce {
             Ok(sauce) => {
                 if let Some(sauce) = sauce {
                     wtr.serialize(sauce)?;
                 }
             }
-            Err(e) => eprintln!("{}: {}", file, e),
+            Err(e) => report(&file, &e),
         }
     }
     wtr.flush()?;
     Ok(())
 }
 
-fn export_json(values: Values, json_file: &str) -> Result<(), Box<dyn Error>> {
+fn export_json(values: Values, entries: bool, json_file: &str) -> Result<(), Box<dyn Error>> {
     let mut vec = Vec::new();
-    for file in values {
-        match Sauce::from_file(file) {
+    for (file, sauce) in read_sauces(values, entries) {
+        match sauce {
             Ok(sauce) => {
                 if let Some(sauce) = sauce {
-                    vec.push(sauce);
+                    vec.push(sauce.to_json_value()?);
                 }
             }
-            Err(e) => eprintln!("{}: {}", file, e),
+            Err(e) => report(&file, &e),
         }
     }
     let file = File::create(json_file)?;
@@ -654,6 +567,20 @@ fn export_json(values: Values, json_file: &str) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+fn list_entries(values: Values) -> Result<(), Box<dyn Error>> {
+    for file in values {
+        match Archive::from_file(file).and_then(|mut archive| archive.entries()) {
+            Ok(entries) => {
+                for entry in entries {
+                    println!("{}/{}", file, entry);
+                }
+            }
+            Err(e) => report(file, &e),
+        }
+    }
+    Ok(())
+}
+
 fn import_csv(file: &str) -> Result<(), Box<dyn Error>> {
     let mut rdr = csv::Reader::from_path(file)?;
     for result in rdr.deserialize() {
@@ -661,7 +588,7 @@ fn import_csv(file: &str) -> Result<(), Box<dyn Error>> {
         if let Some(file) = sauce.file.clone() {
             match sauce.add_to_file(&file) {
                 Ok(_) => println!("{}: Updated", file),
-                Err(e) => eprintln!("{}: {}", file, e),
+                Err(e) => report(&file, &e),
             }
         }
     }
@@ -675,7 +602,7 @@ fn import_json(file: &str) -> Result<(), Box<dyn Error>> {
         if let Some(file) = sauce.file.clone() {
             match sauce.add_to_file(&file) {
                 Ok(_) => println!("{}: Updated", file),
-                Err(e) => eprintln!("{}: {}", file, e),
+                Err(e) => report(&file, &e),
             }
         }
     }
@@ -686,8 +613,14 @@ pub fn sauce_opts(matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
     if matches.is_present("sauce_remove") {
         sauce_remove(matches.values_of("files").unwrap())?;
     }
+    if matches.is_present("repair") {
+        repair(matches.values_of("files").unwrap())?;
+    }
     if matches.is_present("filetype") {
-        filetype(matches.values_of("files").unwrap())?;
+        filetype(
+            matches.values_of("files").unwrap(),
+            matches.is_present("overwrite"),
+        )?;
     }
     if matches.is_present("title") {
         title(
@@ -801,20 +734,37 @@ pub fn sauce_opts(matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
         )?;
     }
     if matches.is_present("no_sauce") {
-        no_sauce(matches.values_of("files").unwrap())?;
+        no_sauce(
+            matches.values_of("files").unwrap(),
+            matches.is_present("entries"),
+        )?;
+    }
+    if matches.is_present("list_entries") {
+        list_entries(matches.values_of("files").unwrap())?;
+    }
+    if matches.is_present("lint") {
+        lint(
+            matches.values_of("files").unwrap(),
+            matches.is_present("entries"),
+        )?;
     }
     if matches.is_present("sauce_display") {
-        sauce_display(matches.values_of("files").unwrap())?;
+        sauce_display(
+            matches.values_of("files").unwrap(),
+            matches.is_present("entries"),
+        )?;
     }
     if matches.is_present("export_csv") {
         export_csv(
             matches.values_of("files").unwrap(),
+            matches.is_present("entries"),
             matches.value_of("export_csv").unwrap(),
         )?;
     }
     if matches.is_present("export_json") {
         export_json(
             matches.values_of("files").unwrap(),
+            matches.is_present("entries"),
             matches.value_of("export_json").unwrap(),
         )?;
     }
@@ -0,0 +1,58 @@
+use crate::report;
+use clap::ArgMatches;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use tmode::archive::{is_archive, Archive};
+use tmode::pack::{Pack, PackFile};
+
+fn add_path(pack: &mut Pack, path: &str) -> Result<(), Box<dyn Error>> {
+    if Path::new(path).is_dir() {
+        let mut files = Vec::new();
+        for entry in fs::read_dir(path)? {
+            let file = entry?.path();
+            if file.is_file() {
+                files.push(file.to_string_lossy().to_string());
+            }
+        }
+        files.sort();
+        for file in files.iter() {
+            if let Err(e) = add_path(pack, file) {
+                report(file, &*e);
+            }
+        }
+    } else if is_archive(path) {
+        let mut archive = Archive::from_file(path)?;
+        for entry in archive.entries()? {
+            let key = archive.key(&entry);
+            match archive
+                .read_entry(&entry)
+                .and_then(|bytes| PackFile::from_bytes(&key, bytes))
+            {
+                Ok(file) => pack.files.push(file),
+                Err(e) => report(&key, &e),
+            }
+        }
+    } else {
+        pack.add_file(path)?;
+    }
+    Ok(())
+}
+
+pub fn sheet_opts(matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let output_file = matches.value_of("output").unwrap();
+    let mut pack = Pack::new(output_file);
+    for path in matches.values_of("files").unwrap() {
+        if let Err(e) = add_path(&mut pack, path) {
+            report(path, &*e);
+        }
+    }
+    match pack.contact_sheet() {
+        Some(sheet) => {
+            sheet.as_png(output_file)?;
+            println!("{}: Added {} files", output_file, pack.files.len());
+        }
+        None => eprintln!("{}: No files could be rendered", output_file),
+    }
+    Ok(())
+}
@@ -1,21 +1,29 @@
 use crate::ascii;
+use crate::error::{Context, Result};
 use std::error::Error;
 use std::fs::File;
 use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
 
-pub fn read_file_to_bytes(file: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+pub fn read_file_to_bytes<P: AsRef<Path>>(file: P) -> Result<Vec<u8>> {
+    let reader = File::open(&file).with_path(&file)?;
+    read_to_bytes(BufReader::new(reader)).with_path(&file)
+}
+
+pub fn read_to_bytes<R: Read>(mut reader: R) -> Result<Vec<u8>> {
     let mut bytes = Vec::new();
-    let file = File::open(file)?;
-    let mut buffer = BufReader::new(file);
-    buffer.read_to_end(&mut bytes)?;
+    reader.read_to_end(&mut bytes)?;
     Ok(bytes)
 }
 
-pub fn write_bytes_to_file(bytes: &[u8], file: &str) -> Result<(), Box<dyn Error>> {
-    let file = File::create(file)?;
-    let mut buffer = BufWriter::new(file);
-    buffer.write_all(bytes)?;
-    buffer.flush()?;
+pub fn write_bytes_to_file<P: AsRef<Path>>(bytes: &[u8], file: P) -> Result<()> {
+    let writer = File::create(&file).with_path(&file)?;
+    write_bytes(bytes, BufWriter::new(writer)).with_path(&file)
+}
+
+pub fn write_bytes<W: Write>(bytes: &[u8], mut writer: W) -> Result<()> {
+    writer.write_all(bytes)?;
+    writer.flush()?;
     Ok(())
 }
 
@@ -27,7 +35,7 @@ pub enum BytesError {
 impl std::fmt::Display for BytesError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
-            BytesError::TooLarge => writeln!(f, "Bytes too large"),
+            BytesError::TooLarge => write!(f, "Bytes too large"),
         }
     }
 }
@@ -41,7 +49,7 @@ pub trait VecOps<T> {
     fn pad_with_value(&mut self, length: usize, value: T) -> &Self;
     fn pad_with_spaces(&mut self, length: usize) -> &Self;
     fn pad_with_nulls(&mut self, length: usize) -> &Self;
-    fn write_to_slice(&self, slice: &mut [u8]) -> Result<(), Box<dyn Error>>;
+    fn write_to_slice(&self, slice: &mut [u8]) -> std::result::Result<(), BytesError>;
 }
 
 pub trait AsUSize {
@@ -87,9 +95,9 @@ impl VecOps<u8> for Vec<u8> {
         self.pad_with_value(length, ascii::NULL)
     }
 
-    fn write_to_slice(&self, bytes: &mut [u8]) -> Result<(), Box<dyn Error>> {
+    fn write_to_slice(&self, bytes: &mut [u8]) -> std::result::Result<(), BytesError> {
         if self.len() > bytes.len() {
-            return Err(Box::new(BytesError::TooLarge));
+            return Err(BytesError::TooLarge);
         }
         for (i, c) in self.iter().enumerate() {
             bytes[i] = *c;
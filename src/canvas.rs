@@ -0,0 +1,230 @@
+use crate::ascii;
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct Cell {
+    pub code: u8,
+    pub fg: u8,
+    pub bg: u8,
+    pub blink: bool,
+    pub font: Option<u8>,
+}
+
+impl Default for Cell {
+    fn default() -> Cell {
+        Cell {
+            code: ascii::SPACE,
+            fg: 7,
+            bg: 0,
+            blink: false,
+            font: None,
+        }
+    }
+}
+
+impl Cell {
+    pub fn attribute(&self) -> u8 {
+        let blink = if self.blink { 128 } else { 0 };
+        self.fg | (self.bg << 4) | blink
+    }
+
+    pub fn is_blank(&self) -> bool {
+        (self.code == 0 || self.code == ascii::SPACE || self.code == 255)
+            && self.attribute() == 7
+            && self.font.is_none()
+    }
+}
+
+#[derive(Clone)]
+pub struct Canvas {
+    pub width: usize,
+    pub height: usize,
+    pub cells: Vec<Cell>,
+    pub ice_colors: bool,
+}
+
+impl Canvas {
+    pub fn new(width: usize, height: usize) -> Canvas {
+        Canvas {
+            width,
+            height,
+            cells: vec![Cell::default(); width * height],
+            ice_colors: false,
+        }
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<&Cell> {
+        if x < self.width && y < self.height {
+            Some(&self.cells[y * self.width + x])
+        } else {
+            None
+        }
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, cell: Cell) {
+        if x >= self.width {
+            return;
+        }
+        if let Some(height) = y.checked_add(1) {
+            self.grow(height);
+        }
+        if y < self.height {
+            self.cells[y * self.width + x] = cell;
+        }
+    }
+
+    pub fn grow(&mut self, height: usize) {
+        if height > self.height {
+            if let Some(length) = self.width.checked_mul(height) {
+                self.height = height;
+                self.cells.resize(length, Cell::default());
+            }
+        }
+    }
+
+    pub fn fill_row(&mut self, y: usize, from_x: usize, to_x: usize, cell: Cell) {
+        for x in from_x..to_x.min(self.width) {
+            self.set(x, y, cell);
+        }
+    }
+
+    pub fn insert_rows(&mut self, y: usize, count: usize, cell: Cell) {
+        self.grow(y);
+        let start = y * self.width;
+        let row = vec![cell; self.width * count];
+        self.cells.splice(start..start, row);
+        self.height += count;
+    }
+
+    pub fn remove_rows(&mut self, y: usize, count: usize) {
+        if y >= self.height {
+            return;
+        }
+        let count = count.min(self.height - y);
+        let start = y * self.width;
+        self.cells.drain(start..start + count * self.width);
+        self.height -= count;
+    }
+
+    pub fn crop(&self, x: usize, y: usize, width: usize, height: usize) -> Canvas {
+        let x = x.min(self.width);
+        let y = y.min(self.height);
+        let width = width.min(self.width - x);
+        let height = height.min(self.height - y);
+        let mut canvas = Canvas::new(width, height);
+        canvas.ice_colors = self.ice_colors;
+        for row in 0..height {
+            let start = (y + row) * self.width + x;
+            canvas.cells[row * width..(row + 1) * width]
+                .copy_from_slice(&self.cells[start..start + width]);
+        }
+        canvas
+    }
+
+    pub fn trim(&self) -> Canvas {
+        let rows: Vec<&[Cell]> = self.cells.chunks(self.width.max(1)).collect();
+        let height = rows
+            .iter()
+            .rposition(|row| !row.iter().all(Cell::is_blank))
+            .map_or(1, |y| y + 1);
+        let width = rows
+            .iter()
+            .filter_map(|row| row.iter().rposition(|cell| !cell.is_blank()))
+            .max()
+            .map_or(1, |x| x + 1);
+        self.crop(0, 0, width, height)
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.height = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_ignores_unrepresentable_rows() {
+        let mut canvas = Canvas::new(80, 0);
+        canvas.set(0, usize::MAX, Cell::default());
+        canvas.set(0, usize::MAX / 2, Cell::default());
+        assert_eq!(canvas.height, 0);
+    }
+
+    fn numbered(width: usize, height: usize) -> Canvas {
+        let mut canvas = Canvas::new(width, height);
+        for (i, cell) in canvas.cells.iter_mut().enumerate() {
+            cell.code = b'a' + i as u8;
+        }
+        canvas
+    }
+
+    fn codes(canvas: &Canvas) -> Vec<u8> {
+        canvas.cells.iter().map(|cell| cell.code).collect()
+    }
+
+    #[test]
+    fn blank_cells() {
+        assert!(Cell::default().is_blank());
+        for code in [0, 255].iter() {
+            let cell = Cell {
+                code: *code,
+                ..Cell::default()
+            };
+            assert!(cell.is_blank());
+        }
+        let blinking = Cell {
+            blink: true,
+            ..Cell::default()
+        };
+        let background = Cell {
+            bg: 1,
+            ..Cell::default()
+        };
+        let font = Cell {
+            font: Some(1),
+            ..Cell::default()
+        };
+        assert!(!blinking.is_blank());
+        assert!(!background.is_blank());
+        assert!(!font.is_blank());
+    }
+
+    #[test]
+    fn crop_clamps_to_canvas() {
+        let mut canvas = numbered(4, 3);
+        canvas.ice_colors = true;
+        let cropped = canvas.crop(1, 1, 2, 5);
+        assert_eq!((cropped.width, cropped.height), (2, 2));
+        assert_eq!(codes(&cropped), b"fgjk");
+        assert!(cropped.ice_colors);
+        let outside = canvas.crop(10, 10, 2, 2);
+        assert_eq!((outside.width, outside.height), (0, 0));
+    }
+
+    #[test]
+    fn trim_removes_blank_edges() {
+        let mut canvas = Canvas::new(5, 4);
+        canvas.set(
+            1,
+            1,
+            Cell {
+                code: b'x',
+                ..Cell::default()
+            },
+        );
+        canvas.set(
+            2,
+            0,
+            Cell {
+                bg: 4,
+                ..Cell::default()
+            },
+        );
+        let trimmed = canvas.trim();
+        assert_eq!((trimmed.width, trimmed.height), (3, 2));
+        let blank = Canvas::new(5, 4).trim();
+        assert_eq!((blank.width, blank.height), (1, 1));
+    }
+}
@@ -0,0 +1,353 @@
+use crate::bytes::AsUSize;
+use crate::detect::Confidence;
+use crate::sauce::{DataType, FileType};
+
+static STRONG_MAGIC_LENGTH: usize = 4;
+static DIB_HEADER_SIZES: &[usize] = &[12, 16, 40, 52, 56, 64, 108, 124];
+static DWG_VERSIONS: &[&[u8]] = &[
+    b"AC1.2\0", b"AC1.40", b"AC1.50", b"AC2.10", b"AC1001", b"AC1002", b"AC1003", b"AC1004",
+    b"AC1006", b"AC1009", b"AC1012", b"AC1014", b"AC1015", b"AC1018", b"AC1021", b"AC1024",
+    b"AC1027", b"AC1032",
+];
+
+pub struct Magic {
+    pub offset: usize,
+    pub bytes: &'static [u8],
+    pub check: fn(&[u8]) -> bool,
+    pub datatype: DataType,
+    pub filetype: FileType,
+}
+
+impl Magic {
+    pub fn confidence(&self) -> Confidence {
+        if self.bytes.len() >= STRONG_MAGIC_LENGTH {
+            Confidence::High
+        } else {
+            Confidence::Medium
+        }
+    }
+}
+
+fn always(_: &[u8]) -> bool {
+    true
+}
+
+fn is_pcx(bytes: &[u8]) -> bool {
+    match bytes {
+        [_, version, 1, depth, ..] => *version <= 5 && [1, 2, 4, 8].contains(depth),
+        _ => false,
+    }
+}
+
+fn is_bmp(bytes: &[u8]) -> bool {
+    let field = |offset: usize| bytes.get(offset..offset + 4).map(AsUSize::as_usize);
+    match (field(2), field(6), field(10), field(14)) {
+        (Some(filesize), Some(0), Some(data_offset), Some(header_size)) => {
+            DIB_HEADER_SIZES.contains(&header_size)
+                && data_offset >= 14 + header_size
+                && filesize >= data_offset
+        }
+        _ => false,
+    }
+}
+
+fn is_iff_image(bytes: &[u8]) -> bool {
+    matches!(bytes.get(8..12), Some(b"ILBM") | Some(b"PBM "))
+}
+
+fn is_tga(bytes: &[u8]) -> bool {
+    let field = |offset: usize| bytes.get(offset..offset + 2).map(AsUSize::as_usize);
+    match (bytes.get(0..18), field(12), field(14)) {
+        (Some(header), Some(width), Some(height)) => {
+            let colormapped = matches!(header[2], 1 | 9);
+            let colormap_valid = match header[1] {
+                0 => !colormapped && header[3..8].iter().all(|byte| *byte == 0),
+                1 => [15, 16, 24, 32].contains(&header[7]),
+                _ => false,
+            };
+            colormap_valid
+                && [1, 2, 3, 9, 10, 11].contains(&header[2])
+                && [8, 15, 16, 24, 32].contains(&header[16])
+                && header[17] & 0xc0 == 0
+                && width > 0
+                && height > 0
+        }
+        _ => false,
+    }
+}
+
+fn is_wpg_bitmap(bytes: &[u8]) -> bool {
+    bytes.get(8..10) == Some(&[1, 0x16])
+}
+
+fn is_mpeg(bytes: &[u8]) -> bool {
+    matches!(bytes.get(3), Some(0xb3) | Some(0xba))
+}
+
+fn is_avi(bytes: &[u8]) -> bool {
+    bytes.get(8..12) == Some(b"AVI ")
+}
+
+fn is_wav(bytes: &[u8]) -> bool {
+    bytes.get(8..12) == Some(b"WAVE")
+}
+
+fn is_dxf(bytes: &[u8]) -> bool {
+    let start = bytes.iter().position(|byte| !byte.is_ascii_whitespace());
+    match start {
+        Some(start) => {
+            let head = &bytes[start..bytes.len().min(start + 32)];
+            head.starts_with(b"0") && head.windows(7).any(|window| window == b"SECTION")
+        }
+        None => false,
+    }
+}
+
+fn is_dwg(bytes: &[u8]) -> bool {
+    bytes
+        .get(0..6)
+        .map_or(false, |version| DWG_VERSIONS.contains(&version))
+}
+
+fn is_3ds(bytes: &[u8]) -> bool {
+    bytes.get(6..8) == Some(&[2, 0])
+}
+
+fn is_669(bytes: &[u8]) -> bool {
+    match (
+        bytes.get(110..113),
+        bytes.get(113..241),
+        bytes.get(369..497),
+    ) {
+        (Some(&[samples, patterns, loop_order]), Some(orders), Some(breaks)) => {
+            samples <= 64
+                && (1..=128).contains(&patterns)
+                && loop_order < 128
+                && orders
+                    .iter()
+                    .all(|order| *order == 0xff || *order < patterns)
+                && breaks.iter().all(|row| *row < 64)
+        }
+        _ => false,
+    }
+}
+
+fn is_stm(bytes: &[u8]) -> bool {
+    bytes.get(28..30) == Some(&[0x1a, 2])
+}
+
+fn is_mtm(bytes: &[u8]) -> bool {
+    bytes.get(3).map_or(false, |version| version >> 4 == 1)
+}
+
+fn is_amf(bytes: &[u8]) -> bool {
+    bytes
+        .get(3)
+        .map_or(false, |version| (10..=14).contains(version))
+}
+
+fn is_16_bit_patch(bytes: &[u8]) -> bool {
+    bytes.get(294).map_or(false, |modes| modes & 1 != 0)
+}
+
+fn is_arc(bytes: &[u8]) -> bool {
+    bytes
+        .get(1)
+        .map_or(false, |method| (1..=9).contains(method))
+        && is_arc_name(bytes)
+}
+
+fn is_pak(bytes: &[u8]) -> bool {
+    bytes
+        .get(1)
+        .map_or(false, |method| (10..=11).contains(method))
+        && is_arc_name(bytes)
+}
+
+fn is_arc_name(bytes: &[u8]) -> bool {
+    match bytes.get(2..15) {
+        Some(name) => match name.iter().position(|byte| *byte == 0) {
+            Some(length) => length > 0 && name[..length].iter().all(u8::is_ascii_graphic),
+            None => false,
+        },
+        None => false,
+    }
+}
+
+fn is_lzh(bytes: &[u8]) -> bool {
+    bytes.get(6) == Some(&b'-')
+}
+
+fn is_fli(bytes: &[u8]) -> bool {
+    bytes.get(4..6).map(AsUSize::as_usize) == Some(0xaf11)
+}
+
+fn is_flc(bytes: &[u8]) -> bool {
+    bytes.get(4..6).map(AsUSize::as_usize) == Some(0xaf12)
+}
+
+macro_rules! magic {
+    ($offset:expr, $bytes:expr, $check:expr, $datatype:ident, $filetype:ident) => {
+        Magic {
+            offset: $offset,
+            bytes: $bytes,
+            check: $check,
+            datatype: DataType::$datatype,
+            filetype: FileType::$filetype,
+        }
+    };
+}
+
+pub static MAGIC: &[Magic] = &[
+    magic!(0, b"XBIN\x1a", always, XBin, XBin),
+    magic!(0, b"\x18TUNDRA24", always, Character, TundraDraw),
+    magic!(0, b"!|", always, Character, RIPScript),
+    magic!(0, b"GIF87a", always, Bitmap, GIF),
+    magic!(0, b"GIF89a", always, Bitmap, GIF),
+    magic!(0, b"\x0a", is_pcx, Bitmap, PCX),
+    magic!(0, b"FORM", is_iff_image, Bitmap, LBMOrIFF),
+    magic!(0, b"", is_fli, Bitmap, FLI),
+    magic!(0, b"", is_flc, Bitmap, FLC),
+    magic!(0, b"BM", is_bmp, Bitmap, BMP),
+    magic!(0, b"\xffWPC", is_wpg_bitmap, Bitmap, WPGBitmap),
+    magic!(0, b"\xffWPC", always, Vector, WPGVector),
+    magic!(0, b"\x89PNG\r\n\x1a\n", always, Bitmap, PNG),
+    magic!(0, b"\xff\xd8\xff", always, Bitmap, JPG),
+    magic!(0, b"\x00\x00\x01", is_mpeg, Bitmap, MPG),
+    magic!(4, b"ftyp", always, Bitmap, MPG),
+    magic!(0, b"RIFF", is_avi, Bitmap, AVI),
+    magic!(0, b"AutoCAD Binary DXF\r\n\x1a\x00", always, Vector, DXF),
+    magic!(0, b"", is_dxf, Vector, DXF),
+    magic!(0, b"AC", is_dwg, Vector, DWG),
+    magic!(0, b"\x4d\x4d", is_3ds, Vector, Studio3DS),
+    magic!(1080, b"M.K.", always, Audio, MOD),
+    magic!(1080, b"M!K!", always, Audio, MOD),
+    magic!(1080, b"FLT4", always, Audio, MOD),
+    magic!(1080, b"FLT8", always, Audio, MOD),
+    magic!(1080, b"4CHN", always, Audio, MOD),
+    magic!(1080, b"6CHN", always, Audio, MOD),
+    magic!(1080, b"8CHN", always, Audio, MOD),
+    magic!(0, b"if", is_669, Audio, Renaissance669),
+    magic!(0, b"JN", is_669, Audio, Renaissance669),
+    magic!(20, b"!Scream!", is_stm, Audio, STM),
+    magic!(20, b"BMOD2STM", is_stm, Audio, STM),
+    magic!(44, b"SCRM", always, Audio, S3M),
+    magic!(0, b"MTM", is_mtm, Audio, MTM),
+    magic!(0, b"FAR\xfe", always, Audio, FAR),
+    magic!(0, b"MAS_UTrack_V00", always, Audio, ULT),
+    magic!(0, b"AMF", is_amf, Audio, AMF),
+    magic!(0, b"DDMF", always, Audio, DMF),
+    magic!(0, b"OKTASONG", always, Audio, OKT),
+    magic!(4, b"\\roll\\default", always, Audio, ROL),
+    magic!(0, b"CTMF", always, Audio, CMF),
+    magic!(0, b"MThd", always, Audio, MID),
+    magic!(0, b"SAdT", always, Audio, SADT),
+    magic!(0, b"Creative Voice File\x1a", always, Audio, VOC),
+    magic!(0, b"RIFF", is_wav, Audio, WAV),
+    magic!(0, b"GF1PATCH1", is_16_bit_patch, Audio, PATCH16),
+    magic!(0, b"GF1PATCH1", always, Audio, PATCH8),
+    magic!(0, b"Extended Module: ", always, Audio, XM),
+    magic!(0, b"IMPM", always, Audio, IT),
+    magic!(0, b"PK\x03\x04", always, Archive, ZIP),
+    magic!(0, b"PK\x05\x06", always, Archive, ZIP),
+    magic!(0, b"\x60\xea", always, Archive, ARJ),
+    magic!(2, b"-lh", is_lzh, Archive, LZH),
+    magic!(2, b"-lz", is_lzh, Archive, LZH),
+    magic!(0, b"\x1a", is_arc, Archive, ARC),
+    magic!(257, b"ustar", always, Archive, TAR),
+    magic!(20, b"\xdc\xa7\xc4\xfd", always, Archive, ZOO),
+    magic!(0, b"Rar!\x1a\x07", always, Archive, RAR),
+    magic!(0, b"UC2\x1a", always, Archive, UC2),
+    magic!(0, b"\x1a", is_pak, Archive, PAK),
+    magic!(0, b"HLSQZ", always, Archive, SQZ),
+    magic!(0, b"", is_tga, Bitmap, TGA),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bmp_header(header_size: usize) -> Vec<u8> {
+        let mut bytes = vec![0; 14 + header_size];
+        bytes[0..2].copy_from_slice(b"BM");
+        bytes[2..6].copy_from_slice(&(14 + header_size as u32 + 4).to_le_bytes());
+        bytes[10..14].copy_from_slice(&(14 + header_size as u32).to_le_bytes());
+        bytes[14..18].copy_from_slice(&(header_size as u32).to_le_bytes());
+        bytes
+    }
+
+    #[test]
+    fn bmp_needs_a_dib_header() {
+        assert!(is_bmp(&bmp_header(40)));
+        assert!(is_bmp(&bmp_header(124)));
+        assert!(!is_bmp(&bmp_header(41)));
+        assert!(!is_bmp(b"BMW owners club, est. 1994"));
+        let mut truncated = bmp_header(40);
+        truncated[2..6].copy_from_slice(&20u32.to_le_bytes());
+        assert!(!is_bmp(&truncated));
+    }
+
+    #[test]
+    fn dwg_needs_a_version_string() {
+        assert!(is_dwg(b"AC1015\0\0\0\0\0"));
+        assert!(is_dwg(b"AC2.10\0\0\0\0\0"));
+        assert!(!is_dwg(b"AC1 adapter review"));
+        assert!(!is_dwg(b"AC2"));
+    }
+
+    #[test]
+    fn tracker_headers() {
+        assert!(is_mtm(b"MTM\x10"));
+        assert!(!is_mtm(b"MTM rocks"));
+        assert!(is_amf(b"AMF\x0e"));
+        assert!(!is_amf(b"AMF is a file"));
+    }
+
+    #[test]
+    fn renaissance_669_order_list() {
+        let mut bytes = vec![0; 497];
+        bytes[0..2].copy_from_slice(b"if");
+        bytes[110] = 4;
+        bytes[111] = 2;
+        for order in bytes[113..241].iter_mut() {
+            *order = 0xff;
+        }
+        bytes[113] = 0;
+        bytes[114] = 1;
+        assert!(is_669(&bytes));
+        bytes[115] = 2;
+        assert!(!is_669(&bytes));
+        let text = format!("if you can read this{}", " ".repeat(500));
+        assert!(!is_669(text.as_bytes()));
+    }
+
+    #[test]
+    fn tga_from_header() {
+        let mut header = vec![0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 64, 0, 32, 0, 24, 0x20];
+        assert!(is_tga(&header));
+        header[1] = 1;
+        assert!(!is_tga(&header));
+        header[7] = 24;
+        assert!(is_tga(&header));
+        header[16] = 7;
+        assert!(!is_tga(&header));
+        assert!(!is_tga(b"Hello, this is just a text file"));
+        assert!(!is_tga(&[
+            0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 32, 0, 24, 0
+        ]));
+    }
+
+    #[test]
+    fn short_magic_is_medium_confidence() {
+        let confidence = |filetype: FileType| {
+            let magic = MAGIC.iter().find(|magic| magic.filetype == filetype);
+            magic.unwrap().confidence()
+        };
+        assert_eq!(confidence(FileType::BMP), Confidence::Medium);
+        assert_eq!(confidence(FileType::DWG), Confidence::Medium);
+        assert_eq!(confidence(FileType::MTM), Confidence::Medium);
+        assert_eq!(confidence(FileType::Renaissance669), Confidence::Medium);
+        assert_eq!(confidence(FileType::PNG), Confidence::High);
+        assert_eq!(confidence(FileType::XM), Confidence::High);
+    }
+}
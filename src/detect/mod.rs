@@ -0,0 +1,410 @@
+mod magic;
+mod tinfo;
+use self::magic::MAGIC;
+use self::tinfo::tinfo;
+use crate::error::{Context, Result};
+use crate::sauce::{flags_supported, DataType, FileType, Sauce};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+static HEAD_LENGTH: u64 = 65536;
+static ATASCII_EOL: u8 = 0x9b;
+
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub enum Confidence {
+    Low,
+    Medium,
+    High,
+}
+
+impl std::fmt::Display for Confidence {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Confidence::Low => write!(f, "low"),
+            Confidence::Medium => write!(f, "medium"),
+            Confidence::High => write!(f, "high"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TextFormat {
+    Ansi,
+    Atascii,
+    PCBoard,
+    Pipe,
+    Wildcat,
+    Wwiv,
+}
+
+impl std::fmt::Display for TextFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            TextFormat::Ansi => write!(f, "ansi"),
+            TextFormat::Atascii => write!(f, "atascii"),
+            TextFormat::PCBoard => write!(f, "pcboard"),
+            TextFormat::Pipe => write!(f, "pipe"),
+            TextFormat::Wildcat => write!(f, "wildcat"),
+            TextFormat::Wwiv => write!(f, "wwiv"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Detection {
+    pub datatype: DataType,
+    pub filetype: FileType,
+    pub confidence: Confidence,
+    pub info_1: usize,
+    pub info_2: usize,
+    pub info_3: usize,
+    pub info_4: usize,
+    pub text_format: Option<TextFormat>,
+}
+
+impl Detection {
+    pub fn supports_flags(&self) -> bool {
+        flags_supported(Some(self.datatype), Some(self.filetype))
+    }
+
+    pub fn apply_to_sauce(&self, sauce: &mut Sauce) {
+        sauce.datatype = Some(self.datatype);
+        sauce.filetype = Some(self.filetype);
+        let info = |value: usize| if value > u16::MAX as usize { 0 } else { value };
+        sauce.info_1 = info(self.info_1);
+        sauce.info_2 = info(self.info_2);
+        sauce.info_3 = info(self.info_3);
+        sauce.info_4 = info(self.info_4);
+    }
+}
+
+impl std::fmt::Display for Detection {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}/{} ({} confidence)",
+            self.datatype, self.filetype, self.confidence
+        )
+    }
+}
+
+fn from_magic(bytes: &[u8]) -> Option<(DataType, FileType, Confidence)> {
+    MAGIC
+        .iter()
+        .find(|magic| {
+            bytes.get(magic.offset..magic.offset + magic.bytes.len()) == Some(magic.bytes)
+                && (magic.check)(bytes)
+        })
+        .map(|magic| (magic.datatype, magic.filetype, magic.confidence()))
+}
+
+fn from_sauce(sauce: &Option<Sauce>) -> Option<(DataType, FileType, [usize; 4])> {
+    let sauce = sauce.as_ref()?;
+    let info = [sauce.info_1, sauce.info_2, sauce.info_3, sauce.info_4];
+    Some((sauce.datatype?, sauce.filetype?, info))
+}
+
+fn count_codes(bytes: &[u8], length: usize, is_code: fn(&[u8]) -> bool) -> usize {
+    bytes
+        .windows(length)
+        .filter(|window| is_code(window))
+        .count()
+}
+
+fn is_pcboard_code(window: &[u8]) -> bool {
+    match window {
+        [b'@', b'X', bg, fg] => bg.is_ascii_hexdigit() && fg.is_ascii_hexdigit(),
+        _ => false,
+    }
+}
+
+fn is_wildcat_code(window: &[u8]) -> bool {
+    match window {
+        [b'@', bg, fg, b'@'] => bg.is_ascii_hexdigit() && fg.is_ascii_hexdigit(),
+        _ => false,
+    }
+}
+
+fn is_wwiv_code(window: &[u8]) -> bool {
+    matches!(window, [0x03, b'0'..=b'9'])
+}
+
+fn is_pipe_code(window: &[u8]) -> bool {
+    match window {
+        [b'|', tens @ b'0'..=b'2', units @ b'0'..=b'9'] => {
+            (tens - b'0') * 10 + (units - b'0') <= 23
+        }
+        _ => false,
+    }
+}
+
+fn text_format_from_content(bytes: &[u8]) -> Option<TextFormat> {
+    if bytes.windows(2).any(|window| window == b"\x1b[") {
+        return Some(TextFormat::Ansi);
+    }
+    let counts = [
+        (TextFormat::Pipe, count_codes(bytes, 3, is_pipe_code)),
+        (TextFormat::Wwiv, count_codes(bytes, 2, is_wwiv_code)),
+        (TextFormat::Wildcat, count_codes(bytes, 4, is_wildcat_code)),
+        (TextFormat::PCBoard, count_codes(bytes, 4, is_pcboard_code)),
+    ];
+    match counts.iter().max_by_key(|(_, count)| *count) {
+        Some((format, count)) if *count > 0 => Some(*format),
+        _ if bytes.contains(&ATASCII_EOL) && !bytes.contains(&b'\n') => Some(TextFormat::Atascii),
+        _ => None,
+    }
+}
+
+fn from_content(bytes: &[u8]) -> Option<(DataType, FileType)> {
+    let text_format = text_format_from_content(bytes);
+    match text_format {
+        Some(TextFormat::Ansi) => return Some((DataType::Character, FileType::ANSI)),
+        Some(TextFormat::PCBoard) => return Some((DataType::Character, FileType::PCBoard)),
+        _ => {}
+    }
+    if bytes.windows(2).any(|window| window == b"\x16\x01") {
+        return Some((DataType::Character, FileType::Avatar));
+    }
+    let head = String::from_utf8_lossy(&bytes[..bytes.len().min(256)]).to_ascii_lowercase();
+    if head.contains("<!doctype html") || head.contains("<html") {
+        return Some((DataType::Character, FileType::HTML));
+    }
+    text_format.map(|_| (DataType::Character, FileType::ASCII))
+}
+
+fn text_format(content: &[u8], datatype: DataType, filetype: FileType) -> Option<TextFormat> {
+    match (datatype, filetype) {
+        (DataType::Character, FileType::PCBoard) => Some(TextFormat::PCBoard),
+        (DataType::Character, FileType::ASCII)
+        | (DataType::Character, FileType::ANSI)
+        | (DataType::Character, FileType::ANSImation) => {
+            Some(text_format_from_content(content).unwrap_or(TextFormat::Ansi))
+        }
+        _ => None,
+    }
+}
+
+fn from_extension(file: &str) -> Option<(DataType, FileType)> {
+    let extension = Path::new(file).extension()?.to_str()?.to_ascii_uppercase();
+    let detection = match extension.as_str() {
+        "ASC" | "DIZ" | "NFO" | "TXT" => (DataType::Character, FileType::ASCII),
+        "ANS" => (DataType::Character, FileType::ANSI),
+        "RIP" => (DataType::Character, FileType::RIPScript),
+        "PCB" => (DataType::Character, FileType::PCBoard),
+        "AVT" => (DataType::Character, FileType::Avatar),
+        "HTM" | "HTML" => (DataType::Character, FileType::HTML),
+        "TND" => (DataType::Character, FileType::TundraDraw),
+        "BIN" => (DataType::BinaryText, FileType::Variable(80)),
+        "XB" => (DataType::XBin, FileType::XBin),
+        "GIF" => (DataType::Bitmap, FileType::GIF),
+        "PCX" => (DataType::Bitmap, FileType::PCX),
+        "IFF" | "LBM" => (DataType::Bitmap, FileType::LBMOrIFF),
+        "TGA" => (DataType::Bitmap, FileType::TGA),
+        "FLI" => (DataType::Bitmap, FileType::FLI),
+        "FLC" => (DataType::Bitmap, FileType::FLC),
+        "BMP" => (DataType::Bitmap, FileType::BMP),
+        "GL" => (DataType::Bitmap, FileType::GL),
+        "DL" => (DataType::Bitmap, FileType::DL),
+        "WPG" => (DataType::Bitmap, FileType::WPGBitmap),
+        "PNG" => (DataType::Bitmap, FileType::PNG),
+        "JPEG" | "JPG" => (DataType::Bitmap, FileType::JPG),
+        "MP4" | "MPEG" | "MPG" => (DataType::Bitmap, FileType::MPG),
+        "AVI" => (DataType::Bitmap, FileType::AVI),
+        "DXF" => (DataType::Vector, FileType::DXF),
+        "DWG" => (DataType::Vector, FileType::DWG),
+        "3DS" => (DataType::Vector, FileType::Studio3DS),
+        "MOD" => (DataType::Audio, FileType::MOD),
+        "669" => (DataType::Audio, FileType::Renaissance669),
+        "STM" => (DataType::Audio, FileType::STM),
+        "S3M" => (DataType::Audio, FileType::S3M),
+        "MTM" => (DataType::Audio, FileType::MTM),
+        "FAR" => (DataType::Audio, FileType::FAR),
+        "ULT" => (DataType::Audio, FileType::ULT),
+        "AMF" => (DataType::Audio, FileType::AMF),
+        "DMF" => (DataType::Audio, FileType::DMF),
+        "OKT" => (DataType::Audio, FileType::OKT),
+        "ROL" => (DataType::Audio, FileType::ROL),
+        "CMF" => (DataType::Audio, FileType::CMF),
+        "MID" | "MIDI" => (DataType::Audio, FileType::MID),
+        "SA2" => (DataType::Audio, FileType::SADT),
+        "VOC" => (DataType::Audio, FileType::VOC),
+        "WAV" => (DataType::Audio, FileType::WAV),
+        "PAT" => (DataType::Audio, FileType::PATCH16),
+        "XM" => (DataType::Audio, FileType::XM),
+        "HSC" => (DataType::Audio, FileType::HSC),
+        "IT" => (DataType::Audio, FileType::IT),
+        "ZIP" => (DataType::Archive, FileType::ZIP),
+        "ARJ" => (DataType::Archive, FileType::ARJ),
+        "LHA" | "LZH" => (DataType::Archive, FileType::LZH),
+        "ARC" => (DataType::Archive, FileType::ARC),
+        "TAR" => (DataType::Archive, FileType::TAR),
+        "ZOO" => (DataType::Archive, FileType::ZOO),
+        "RAR" => (DataType::Archive, FileType::RAR),
+        "UC2" => (DataType::Archive, FileType::UC2),
+        "PAK" => (DataType::Archive, FileType::PAK),
+        "SQZ" => (DataType::Archive, FileType::SQZ),
+        _ => return None,
+    };
+    Some(detection)
+}
+
+fn detect_content(content: &[u8], sauce: &Option<Sauce>, file: Option<&str>) -> Option<Detection> {
+    let extension = file.and_then(from_extension);
+    let textual = extension.map_or(true, |(datatype, _)| datatype == DataType::Character);
+    let magic = from_magic(content);
+    let strong_magic = magic.filter(|(_, _, confidence)| *confidence == Confidence::High);
+    let (datatype, filetype, confidence, info) =
+        if let Some((datatype, filetype, confidence)) = strong_magic {
+            let info = tinfo(filetype, content);
+            (datatype, filetype, confidence, info)
+        } else if let Some((datatype, filetype, info)) = from_sauce(sauce) {
+            (datatype, filetype, Confidence::Medium, info)
+        } else if let Some((datatype, filetype, confidence)) = magic {
+            let info = tinfo(filetype, content);
+            (datatype, filetype, confidence, info)
+        } else if let Some((datatype, filetype)) = from_content(content).filter(|_| textual) {
+            (datatype, filetype, Confidence::Medium, [0; 4])
+        } else if let Some((datatype, filetype)) = extension {
+            let info = tinfo(filetype, content);
+            (datatype, filetype, Confidence::Low, info)
+        } else {
+            return None;
+        };
+    let [info_1, info_2, info_3, info_4] = info;
+    Some(Detection {
+        datatype,
+        filetype,
+        confidence,
+        info_1,
+        info_2,
+        info_3,
+        info_4,
+        text_format: text_format(content, datatype, filetype),
+    })
+}
+
+pub fn detect(bytes: &[u8], file: Option<&str>) -> Option<Detection> {
+    let sauce = Sauce::from_bytes(bytes).ok().flatten();
+    let content_end = match &sauce {
+        Some(sauce) => sauce.actual_filesize(),
+        None => bytes.len(),
+    };
+    detect_content(&bytes[..content_end], &sauce, file)
+}
+
+pub fn detect_file(file: &str) -> Result<Option<Detection>> {
+    let mut reader = File::open(file).with_path(file)?;
+    detect_reader(&mut reader, Some(file)).with_path(file)
+}
+
+pub fn detect_reader<R: Read + Seek>(
+    reader: &mut R,
+    file: Option<&str>,
+) -> Result<Option<Detection>> {
+    let sauce = Sauce::from_reader(reader).ok().flatten();
+    let content_end = match &sauce {
+        Some(sauce) => sauce.actual_filesize() as u64,
+        None => reader.seek(SeekFrom::End(0))?,
+    };
+    let mut content = Vec::new();
+    reader.seek(SeekFrom::Start(0))?;
+    reader
+        .take(content_end.min(HEAD_LENGTH))
+        .read_to_end(&mut content)?;
+    Ok(detect_content(&content, &sauce, file))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_sauce(mut bytes: Vec<u8>, datatype: DataType, filetype: FileType) -> Vec<u8> {
+        let mut sauce = Sauce::new();
+        sauce.datatype = Some(datatype);
+        sauce.filetype = Some(filetype);
+        sauce.add_to_bytes(&mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn text_with_weak_magic_is_not_binary() {
+        let detection = detect(b"BMW owners club\r\n", Some("CLUB.TXT")).unwrap();
+        assert_eq!(detection.filetype, FileType::ASCII);
+        assert_eq!(detection.confidence, Confidence::Low);
+        let detection = detect(b"AC2 power\r\n", None);
+        assert!(detection.is_none());
+    }
+
+    #[test]
+    fn weak_magic_does_not_override_sauce() {
+        let mut bytes = vec![0; 64];
+        bytes[0..4].copy_from_slice(b"MTM\x10");
+        let bytes = with_sauce(bytes, DataType::Character, FileType::ANSI);
+        let detection = detect(&bytes, Some("TRACK.MTM")).unwrap();
+        assert_eq!(detection.filetype, FileType::ANSI);
+        assert_eq!(detection.confidence, Confidence::Medium);
+        let detection = detect(&bytes[..64], None).unwrap();
+        assert_eq!(detection.filetype, FileType::MTM);
+        assert_eq!(detection.confidence, Confidence::Medium);
+    }
+
+    #[test]
+    fn strong_magic_overrides_sauce() {
+        let bytes = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+        let bytes = with_sauce(bytes, DataType::Character, FileType::ANSI);
+        let detection = detect(&bytes, None).unwrap();
+        assert_eq!(detection.filetype, FileType::PNG);
+        assert_eq!(detection.confidence, Confidence::High);
+    }
+
+    #[test]
+    fn content_and_extension() {
+        let detection = detect(b"\x1b[1;31mred", None).unwrap();
+        assert_eq!(detection.filetype, FileType::ANSI);
+        let detection = detect(b"@X1Fhello", None).unwrap();
+        assert_eq!(detection.filetype, FileType::PCBoard);
+        let detection = detect(b"plain", Some("ART.ANS")).unwrap();
+        assert_eq!(detection.filetype, FileType::ANSI);
+        assert_eq!(detection.confidence, Confidence::Low);
+    }
+
+    #[test]
+    fn text_formats() {
+        let format = |bytes: &[u8], file: Option<&str>| detect(bytes, file)?.text_format;
+        assert_eq!(format(b"\x1b[0mhi |12", None), Some(TextFormat::Ansi));
+        assert_eq!(format(b"@X1Fhi", None), Some(TextFormat::PCBoard));
+        assert_eq!(format(b"|14hi |17there", None), Some(TextFormat::Pipe));
+        assert_eq!(format(b"\x032hi \x034there", None), Some(TextFormat::Wwiv));
+        assert_eq!(format(b"@1F@hi @4E@there", None), Some(TextFormat::Wildcat));
+        assert_eq!(
+            format(b"READY\x9bHELLO\x9b", None),
+            Some(TextFormat::Atascii)
+        );
+        assert_eq!(format(b"plain", Some("README.TXT")), Some(TextFormat::Ansi));
+        assert_eq!(format(b"|14pipe", Some("MENU.ANS")), Some(TextFormat::Pipe));
+        assert_eq!(format(b"GIF89a", None), None);
+    }
+
+    #[test]
+    fn flags_follow_detection() {
+        let bytes = with_sauce(b"hi".to_vec(), DataType::Character, FileType::ANSI);
+        assert!(detect(&bytes, None).unwrap().supports_flags());
+        let bytes = with_sauce(b"hi".to_vec(), DataType::Bitmap, FileType::GIF);
+        assert!(!detect(&bytes, None).unwrap().supports_flags());
+        let bytes = with_sauce(b"GIF89a".to_vec(), DataType::Character, FileType::ANSI);
+        assert!(!detect(&bytes, None).unwrap().supports_flags());
+    }
+
+    #[test]
+    fn oversized_info_is_not_truncated() {
+        let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+        png.extend_from_slice(&70000u32.to_be_bytes());
+        png.extend_from_slice(&[0, 0, 0, 0x10, 8, 2]);
+        let detection = detect(&png, None).unwrap();
+        assert_eq!(detection.info_1, 70000);
+        let mut sauce = Sauce::new();
+        detection.apply_to_sauce(&mut sauce);
+        assert_eq!((sauce.info_1, sauce.info_2, sauce.info_3), (0, 16, 24));
+    }
+}
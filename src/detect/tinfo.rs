@@ -0,0 +1,172 @@
+use crate::bytes::AsUSize;
+use crate::sauce::FileType;
+
+fn le(bytes: &[u8], offset: usize, length: usize) -> Option<usize> {
+    bytes.get(offset..offset + length).map(AsUSize::as_usize)
+}
+
+fn be(bytes: &[u8], offset: usize, length: usize) -> Option<usize> {
+    let slice = bytes.get(offset..offset + length)?;
+    Some(
+        slice
+            .iter()
+            .fold(0, |value, byte| (value << 8) | *byte as usize),
+    )
+}
+
+fn gif_table_depth(bytes: &[u8]) -> Option<usize> {
+    let mut i = 13;
+    loop {
+        match bytes.get(i)? {
+            0x21 => {
+                i += 2;
+                while *bytes.get(i)? != 0 {
+                    i += *bytes.get(i)? as usize + 1;
+                }
+                i += 1;
+            }
+            0x2c => {
+                let packed = *bytes.get(i + 9)?;
+                return Some((packed & 7) as usize + 1);
+            }
+            _ => return None,
+        }
+    }
+}
+
+fn gif(bytes: &[u8]) -> Option<[usize; 4]> {
+    let packed = *bytes.get(10)?;
+    let depth = if packed & 0x80 != 0 {
+        (packed & 7) as usize + 1
+    } else {
+        gif_table_depth(bytes).unwrap_or(((packed >> 4) & 7) as usize + 1)
+    };
+    Some([le(bytes, 6, 2)?, le(bytes, 8, 2)?, depth, 0])
+}
+
+fn pcx(bytes: &[u8]) -> Option<[usize; 4]> {
+    let width = le(bytes, 8, 2)?.checked_sub(le(bytes, 4, 2)?)? + 1;
+    let height = le(bytes, 10, 2)?.checked_sub(le(bytes, 6, 2)?)? + 1;
+    let depth = *bytes.get(3)? as usize * *bytes.get(65)? as usize;
+    Some([width, height, depth, 0])
+}
+
+fn iff(bytes: &[u8]) -> Option<[usize; 4]> {
+    let mut i = 12;
+    while i + 8 <= bytes.len() {
+        let length = be(bytes, i + 4, 4)?;
+        if &bytes[i..i + 4] == b"BMHD" {
+            let planes = *bytes.get(i + 16)? as usize;
+            return Some([be(bytes, i + 8, 2)?, be(bytes, i + 10, 2)?, planes, 0]);
+        }
+        i += 8 + length + length % 2;
+    }
+    None
+}
+
+fn tga(bytes: &[u8]) -> Option<[usize; 4]> {
+    Some([
+        le(bytes, 12, 2)?,
+        le(bytes, 14, 2)?,
+        *bytes.get(16)? as usize,
+        0,
+    ])
+}
+
+fn fli(bytes: &[u8]) -> Option<[usize; 4]> {
+    Some([le(bytes, 8, 2)?, le(bytes, 10, 2)?, le(bytes, 12, 2)?, 0])
+}
+
+fn bmp(bytes: &[u8]) -> Option<[usize; 4]> {
+    if le(bytes, 14, 4)? == 12 {
+        return Some([le(bytes, 18, 2)?, le(bytes, 20, 2)?, le(bytes, 24, 2)?, 0]);
+    }
+    let height = le(bytes, 22, 4)? as u32 as i32;
+    Some([
+        le(bytes, 18, 4)?,
+        height.unsigned_abs() as usize,
+        le(bytes, 28, 2)?,
+        0,
+    ])
+}
+
+fn png(bytes: &[u8]) -> Option<[usize; 4]> {
+    let channels = match bytes.get(25)? {
+        2 => 3,
+        4 => 2,
+        6 => 4,
+        _ => 1,
+    };
+    let depth = *bytes.get(24)? as usize * channels;
+    Some([be(bytes, 16, 4)?, be(bytes, 20, 4)?, depth, 0])
+}
+
+fn jpg(bytes: &[u8]) -> Option<[usize; 4]> {
+    let mut i = 2;
+    while i + 4 <= bytes.len() {
+        if bytes[i] != 0xff {
+            return None;
+        }
+        let marker = bytes[i + 1];
+        let length = be(bytes, i + 2, 2)?;
+        if (0xc0..=0xcf).contains(&marker) && ![0xc4, 0xc8, 0xcc].contains(&marker) {
+            let depth = *bytes.get(i + 4)? as usize * *bytes.get(i + 9)? as usize;
+            return Some([be(bytes, i + 7, 2)?, be(bytes, i + 5, 2)?, depth, 0]);
+        }
+        i += 2 + length;
+    }
+    None
+}
+
+fn mpg(bytes: &[u8]) -> Option<[usize; 4]> {
+    let start = bytes
+        .windows(4)
+        .position(|window| window == b"\x00\x00\x01\xb3")?;
+    let size = be(bytes, start + 4, 3)?;
+    Some([size >> 12, size & 0xfff, 0, 0])
+}
+
+fn avi(bytes: &[u8]) -> Option<[usize; 4]> {
+    if bytes.get(24..28)? != b"avih" {
+        return None;
+    }
+    Some([le(bytes, 64, 4)?, le(bytes, 68, 4)?, 0, 0])
+}
+
+fn xbin(bytes: &[u8]) -> Option<[usize; 4]> {
+    Some([le(bytes, 5, 2)?, le(bytes, 7, 2)?, 0, 0])
+}
+
+pub fn tinfo(filetype: FileType, bytes: &[u8]) -> [usize; 4] {
+    let info = match filetype {
+        FileType::GIF => gif(bytes),
+        FileType::PCX => pcx(bytes),
+        FileType::LBMOrIFF => iff(bytes),
+        FileType::TGA => tga(bytes),
+        FileType::FLI | FileType::FLC => fli(bytes),
+        FileType::BMP => bmp(bytes),
+        FileType::PNG => png(bytes),
+        FileType::JPG => jpg(bytes),
+        FileType::MPG => mpg(bytes),
+        FileType::AVI => avi(bytes),
+        FileType::XBin => xbin(bytes),
+        _ => None,
+    };
+    info.unwrap_or([0; 4])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bitmap_dimensions() {
+        let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+        png.extend_from_slice(&[0, 0, 2, 0x80, 0, 0, 0, 0x10, 8, 2]);
+        assert_eq!(tinfo(FileType::PNG, &png), [640, 16, 24, 0]);
+        let tga = [0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 64, 0, 32, 0, 24, 0x20];
+        assert_eq!(tinfo(FileType::TGA, &tga), [64, 32, 24, 0]);
+        let gif = b"GIF89a\x40\x01\xc8\x00\xf7\x00\x00";
+        assert_eq!(tinfo(FileType::GIF, gif), [320, 200, 8, 0]);
+    }
+}
@@ -0,0 +1,279 @@
+use crate::archive::ArchiveError;
+use crate::font::FontError;
+use crate::render::RenderError;
+use crate::sauce::SauceError;
+use std::path::{Path, PathBuf};
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    Io {
+        source: std::io::Error,
+        path: Option<PathBuf>,
+        offset: Option<usize>,
+        field: Option<&'static str>,
+    },
+    Sauce {
+        kind: SauceError,
+        path: Option<PathBuf>,
+        offset: Option<usize>,
+        field: Option<&'static str>,
+    },
+    Font {
+        kind: FontError,
+        path: Option<PathBuf>,
+        offset: Option<usize>,
+        field: Option<&'static str>,
+    },
+    Render {
+        kind: RenderError,
+        path: Option<PathBuf>,
+        offset: Option<usize>,
+        field: Option<&'static str>,
+    },
+    Archive {
+        kind: ArchiveError,
+        path: Option<PathBuf>,
+        offset: Option<usize>,
+        field: Option<&'static str>,
+    },
+}
+
+impl Error {
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Error::Io { path, .. }
+            | Error::Sauce { path, .. }
+            | Error::Font { path, .. }
+            | Error::Render { path, .. }
+            | Error::Archive { path, .. } => path.as_deref(),
+        }
+    }
+
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            Error::Io { offset, .. }
+            | Error::Sauce { offset, .. }
+            | Error::Font { offset, .. }
+            | Error::Render { offset, .. }
+            | Error::Archive { offset, .. } => *offset,
+        }
+    }
+
+    pub fn field(&self) -> Option<&'static str> {
+        match self {
+            Error::Io { field, .. }
+            | Error::Sauce { field, .. }
+            | Error::Font { field, .. }
+            | Error::Render { field, .. }
+            | Error::Archive { field, .. } => *field,
+        }
+    }
+
+    pub(crate) fn with_path<P: AsRef<Path>>(mut self, file: P) -> Error {
+        match &mut self {
+            Error::Io { path, .. }
+            | Error::Sauce { path, .. }
+            | Error::Font { path, .. }
+            | Error::Render { path, .. }
+            | Error::Archive { path, .. } => {
+                path.get_or_insert_with(|| file.as_ref().to_path_buf());
+            }
+        }
+        self
+    }
+
+    pub(crate) fn with_offset(mut self, at: usize) -> Error {
+        match &mut self {
+            Error::Io { offset, .. }
+            | Error::Sauce { offset, .. }
+            | Error::Font { offset, .. }
+            | Error::Render { offset, .. }
+            | Error::Archive { offset, .. } => {
+                offset.get_or_insert(at);
+            }
+        }
+        self
+    }
+
+    pub(crate) fn with_field(mut self, name: &'static str) -> Error {
+        match &mut self {
+            Error::Io { field, .. }
+            | Error::Sauce { field, .. }
+            | Error::Font { field, .. }
+            | Error::Render { field, .. }
+            | Error::Archive { field, .. } => {
+                field.get_or_insert(name);
+            }
+        }
+        self
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if let Some(path) = self.path() {
+            write!(f, "{}: ", path.display())?;
+        }
+        match self {
+            Error::Io { source, .. } => write!(f, "{}", source)?,
+            Error::Sauce { kind, .. } => write!(f, "{}", kind)?,
+            Error::Font { kind, .. } => write!(f, "{}", kind)?,
+            Error::Render { kind, .. } => write!(f, "{}", kind)?,
+            Error::Archive { kind, .. } => write!(f, "{}", kind)?,
+        }
+        if let Some(field) = self.field() {
+            write!(f, " in {}", field)?;
+        }
+        if let Some(offset) = self.offset() {
+            write!(f, " at offset {}", offset)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            Error::Render { kind, .. } => kind.source(),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(source: std::io::Error) -> Error {
+        Error::Io {
+            source,
+            path: None,
+            offset: None,
+            field: None,
+        }
+    }
+}
+
+impl From<SauceError> for Error {
+    fn from(kind: SauceError) -> Error {
+        Error::Sauce {
+            kind,
+            path: None,
+            offset: None,
+            field: None,
+        }
+    }
+}
+
+impl From<FontError> for Error {
+    fn from(kind: FontError) -> Error {
+        Error::Font {
+            kind,
+            path: None,
+            offset: None,
+            field: None,
+        }
+    }
+}
+
+impl From<RenderError> for Error {
+    fn from(kind: RenderError) -> Error {
+        Error::Render {
+            kind,
+            path: None,
+            offset: None,
+            field: None,
+        }
+    }
+}
+
+impl From<ArchiveError> for Error {
+    fn from(kind: ArchiveError) -> Error {
+        Error::Archive {
+            kind,
+            path: None,
+            offset: None,
+            field: None,
+        }
+    }
+}
+
+impl From<png::DecodingError> for Error {
+    fn from(error: png::DecodingError) -> Error {
+        Error::from(RenderError::Decoding(error))
+    }
+}
+
+impl From<png::EncodingError> for Error {
+    fn from(error: png::EncodingError) -> Error {
+        Error::from(RenderError::Encoding(error))
+    }
+}
+
+impl From<image::ImageError> for Error {
+    fn from(error: image::ImageError) -> Error {
+        Error::from(RenderError::Gif(error))
+    }
+}
+
+pub(crate) trait Context<T> {
+    fn with_path<P: AsRef<Path>>(self, path: P) -> Result<T>;
+}
+
+impl<T, E: Into<Error>> Context<T> for std::result::Result<T, E> {
+    fn with_path<P: AsRef<Path>>(self, path: P) -> Result<T> {
+        self.map_err(|error| error.into().with_path(path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error as _;
+
+    #[test]
+    fn display_includes_context() {
+        let error = Error::from(SauceError::InvalidDataType)
+            .with_field("datatype")
+            .with_offset(94);
+        assert_eq!(
+            error.to_string(),
+            "Invalid datatype in datatype at offset 94"
+        );
+        assert_eq!(
+            Error::from(FontError::FontNotFound).to_string(),
+            "Font not found"
+        );
+        assert_eq!(
+            error.with_path("art.ans").to_string(),
+            "art.ans: Invalid datatype in datatype at offset 94"
+        );
+    }
+
+    #[test]
+    fn innermost_context_wins() {
+        let result: std::result::Result<(), SauceError> = Err(SauceError::CommentsNotFound);
+        let error = result
+            .with_path("inner.ans")
+            .with_path("outer.zip")
+            .unwrap_err()
+            .with_offset(1)
+            .with_offset(2)
+            .with_field("comments")
+            .with_field("title");
+        assert_eq!(error.path(), Some(Path::new("inner.ans")));
+        assert_eq!(error.offset(), Some(1));
+        assert_eq!(error.field(), Some("comments"));
+    }
+
+    #[test]
+    fn io_errors_keep_their_source() {
+        let io = std::io::Error::new(std::io::ErrorKind::NotFound, "missing");
+        let error = Error::from(io).with_path("art.ans");
+        assert!(matches!(error, Error::Io { .. }));
+        assert_eq!(error.to_string(), "art.ans: missing");
+        assert_eq!(error.source().unwrap().to_string(), "missing");
+        assert!(Error::from(ArchiveError::EntryNotFound).source().is_none());
+    }
+}
@@ -1,6 +1,7 @@
-pub use std::error::Error;
+use std::error::Error;
 
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum FontError {
     FontNotFound,
     ImageNotCorrectDimensions,
@@ -14,13 +15,13 @@ pub enum FontError {
 impl std::fmt::Display for FontError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
-            FontError::FontNotFound => writeln!(f, "Font not found"),
-            FontError::ImageNotCorrectDimensions => writeln!(f, "Image not correct dimensions"),
-            FontError::FileNotCorrectSize => writeln!(f, "File not correct size"),
-            FontError::InvalidXBinFile => writeln!(f, "Invalid XBin file"),
-            FontError::InvalidADFFile => writeln!(f, "Invalid ADF file"),
-            FontError::InvalidIDFFile => writeln!(f, "Invalid IDF file"),
-            FontError::NoFontDataFound => writeln!(f, "No font data found"),
+            FontError::FontNotFound => write!(f, "Font not found"),
+            FontError::ImageNotCorrectDimensions => write!(f, "Image not correct dimensions"),
+            FontError::FileNotCorrectSize => write!(f, "File not correct size"),
+            FontError::InvalidXBinFile => write!(f, "Invalid XBin file"),
+            FontError::InvalidADFFile => write!(f, "Invalid ADF file"),
+            FontError::InvalidIDFFile => write!(f, "Invalid IDF file"),
+            FontError::NoFontDataFound => write!(f, "No font data found"),
         }
     }
 }
@@ -58,6 +58,7 @@ pub static CP866_F16: &[u8; 4096] = include_bytes!("../../fonts/ibm/CP866.F16");
 pub static CP869_F08: &[u8; 2048] = include_bytes!("../../fonts/ibm/CP869.F08");
 pub static CP869_F14: &[u8; 3584] = include_bytes!("../../fonts/ibm/CP869.F14");
 pub static CP869_F16: &[u8; 4096] = include_bytes!("../../fonts/ibm/CP869.F16");
+pub static ATASCII_F08: &[u8; 2048] = include_bytes!("../../fonts/atari/ATASCII.F08");
 pub static PETSCII_SHIFTED: &[u8; 2048] = include_bytes!("../../fonts/c64/PETSCII shifted.F08");
 pub static PETSCII_UNSHIFTED: &[u8; 2048] = include_bytes!("../../fonts/c64/PETSCII unshifted.F08");
 pub static MICRO_KNIGHT_F16: &[u8; 4096] = include_bytes!("../../fonts/amiga/MicroKnight.F16");
@@ -1,12 +1,16 @@
 use crate::render::{Color, Image};
-use std::error::Error;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, Write};
+use std::path::Path;
 pub use std::str::FromStr;
 mod error;
 mod includes;
-use crate::bytes::{read_file_to_bytes, write_bytes_to_file};
+use crate::bytes::{read_to_bytes, write_bytes};
+use crate::error::{Context, Result};
 use crate::sauce::Sauce;
 use crate::string::CP437String;
-use error::FontError;
+use crate::Error;
+pub use error::FontError;
 use includes::*;
 
 pub struct Font {
@@ -50,31 +54,60 @@ impl Font {
         }
     }
 
-    pub fn from_bitmask_file(
-        file: &str,
+    pub fn from_bitmask_file<P: AsRef<Path>>(
+        file: P,
         width: usize,
         height: Option<usize>,
         length: usize,
-    ) -> Result<Font, Box<dyn Error>> {
-        let bytes = read_file_to_bytes(file)?;
+    ) -> Result<Font> {
+        let reader = File::open(&file).with_path(&file)?;
+        Font::from_bitmask_reader(BufReader::new(reader), width, height, length).with_path(&file)
+    }
+
+    pub fn from_bitmask_reader<R: Read>(
+        reader: R,
+        width: usize,
+        height: Option<usize>,
+        length: usize,
+    ) -> Result<Font> {
+        let bytes = read_to_bytes(reader)?;
         let height = match height {
             Some(height) => height,
             None => bytes.len() * 8 / length / width,
         };
         if bytes.len() * 8 < width * height * length {
-            return Err(Box::new(FontError::FileNotCorrectSize));
+            return Err(FontError::FileNotCorrectSize.into());
         }
         let font = Font::new(&bytes, width, height, length);
         Ok(font)
     }
 
-    pub fn from_png_file(
-        file: &str,
+    pub fn from_png_file<P: AsRef<Path>>(
+        file: P,
         width: usize,
         height: Option<usize>,
         length: usize,
-    ) -> Result<Font, Box<dyn Error>> {
+    ) -> Result<Font> {
         let image = Image::from_file(file)?;
+        Font::from_image(&image, width, height, length)
+    }
+
+    pub fn from_png_reader<R: Read>(
+        reader: R,
+        width: usize,
+        height: Option<usize>,
+        length: usize,
+    ) -> Result<Font> {
+        let image = Image::from_reader(reader)?;
+        Font::from_image(&image, width, height, length)
+    }
+
+    pub fn from_image(
+        image: &Image,
+        width: usize,
+        height: Option<usize>,
+        length: usize,
+    ) -> Result<Font> {
         let height = match height {
             Some(height) => height,
             None => image.height / (image.width / width),
@@ -83,7 +116,7 @@ impl Font {
             || image.height % height != 0
             || (image.width / width) * (image.height / height) < length
         {
-            return Err(Box::new(FontError::ImageNotCorrectDimensions));
+            return Err(FontError::ImageNotCorrectDimensions.into());
         }
         let mut glyph_x = 0;
         let mut glyph_y = 0;
@@ -119,7 +152,15 @@ impl Font {
         Ok(font)
     }
 
-    pub fn as_png_file(&self, file: &str, width: usize) -> Result<(), Box<dyn Error>> {
+    pub fn as_png_file<P: AsRef<Path>>(&self, file: P, width: usize) -> Result<()> {
+        self.as_image(width).as_png(file)
+    }
+
+    pub fn write_png<W: Write>(&self, writer: W, width: usize) -> Result<()> {
+        self.as_image(width).write_png(writer)
+    }
+
+    pub fn as_image(&self, width: usize) -> Image {
         let mut image = Image::new(
             width * self.width,
             (self.length as f32 / width as f32).ceil() as usize * self.height,
@@ -144,64 +185,149 @@ impl Font {
                 x += 1;
             }
         }
-        image.as_png(file)?;
-        Ok(())
+        image
+    }
+
+    pub fn from_sauce<P: AsRef<Path>>(file: P) -> Result<Font> {
+        let sauce = Sauce::from_file(file)?;
+        Ok(Font::from_sauce_record(&sauce))
     }
 
-    pub fn from_sauce(file: &str) -> Result<Font, Box<dyn Error>> {
-        let font = match Sauce::from_file(file)? {
-            Some(sauce) => match sauce.font_name.parse::<Font>() {
-                Ok(font) => font,
-                Err(_) => Font::default(),
-            },
+    pub fn from_sauce_reader<R: Read + Seek>(reader: &mut R) -> Result<Font> {
+        let sauce = Sauce::from_reader(reader)?;
+        Ok(Font::from_sauce_record(&sauce))
+    }
+
+    pub(crate) fn from_sauce_record(sauce: &Option<Sauce>) -> Font {
+        match sauce {
+            Some(sauce) => sauce.font_name.parse::<Font>().unwrap_or_default(),
             None => Font::default(),
-        };
-        Ok(font)
+        }
+    }
+
+    pub fn from_adf_file<P: AsRef<Path>>(file: P) -> Result<Font> {
+        let reader = File::open(&file).with_path(&file)?;
+        Font::from_adf_reader(BufReader::new(reader)).with_path(&file)
     }
 
-    pub fn from_adf_file(file: &str) -> Result<Font, Box<dyn Error>> {
-        let bytes = read_file_to_bytes(file)?;
+    pub fn from_adf_reader<R: Read>(reader: R) -> Result<Font> {
+        let bytes = read_to_bytes(reader)?;
         if bytes.len() < 193 + 4096 {
-            return Err(Box::new(FontError::InvalidADFFile));
+            return Err(FontError::InvalidADFFile.into());
         }
         let font = Font::new(&bytes[193..193 + 4096], 8, 16, 256);
         Ok(font)
     }
 
-    pub fn from_idf_file(file: &str) -> Result<Font, Box<dyn Error>> {
-        let bytes = read_file_to_bytes(file)?;
+    pub fn from_idf_file<P: AsRef<Path>>(file: P) -> Result<Font> {
+        let reader = File::open(&file).with_path(&file)?;
+        Font::from_idf_reader(BufReader::new(reader)).with_path(&file)
+    }
+
+    pub fn from_idf_reader<R: Read>(reader: R) -> Result<Font> {
+        let bytes = read_to_bytes(reader)?;
         if bytes.len() < 193 + 4096 {
-            return Err(Box::new(FontError::InvalidIDFFile));
+            return Err(FontError::InvalidIDFFile.into());
         }
         let font_start = bytes.len() - (48 + 4096);
         let font = Font::new(&bytes[font_start..font_start + 4096], 8, 16, 256);
         Ok(font)
     }
 
-    pub fn from_xbin_file(file: &str) -> Result<Font, Box<dyn Error>> {
-        let bytes = read_file_to_bytes(file)?;
+    pub fn from_xbin_file<P: AsRef<Path>>(file: P) -> Result<Font> {
+        let reader = File::open(&file).with_path(&file)?;
+        Font::from_xbin_reader(BufReader::new(reader)).with_path(&file)
+    }
+
+    pub fn from_xbin_reader<R: Read>(reader: R) -> Result<Font> {
+        let bytes = read_to_bytes(reader)?;
         let len = bytes.len();
         if len < 11 || String::from_cp437_bytes(&bytes[0..4]) != "XBIN" {
-            return Err(Box::new(FontError::InvalidXBinFile));
+            return Err(FontError::InvalidXBinFile.into());
         }
         if (bytes[10] >> 1) & 1 == 0 {
-            return Err(Box::new(FontError::NoFontDataFound));
+            return Err(Error::from(FontError::NoFontDataFound)
+                .with_field("flags")
+                .with_offset(10));
         }
         let height = bytes[9] as usize;
         if !(1..=32).contains(&height) {
-            return Err(Box::new(FontError::InvalidXBinFile));
+            return Err(Error::from(FontError::InvalidXBinFile)
+                .with_field("font_size")
+                .with_offset(9));
         }
         let has_palette = bytes[10] & 1 == 1;
         let font_start = if has_palette { 11 + 48 } else { 11 };
         let font_end = font_start + (height * 256);
         if len < font_end {
-            return Err(Box::new(FontError::InvalidXBinFile));
+            return Err(Error::from(FontError::InvalidXBinFile)
+                .with_field("font")
+                .with_offset(font_start));
         }
         let font = Font::new(&bytes[font_start..font_end], 8, height, 256);
         Ok(font)
     }
 
-    pub fn as_bitmask_file(&self, file: &str) -> Result<(), Box<dyn Error>> {
+    pub fn from_syncterm_id(id: u8, height: usize) -> Option<Font> {
+        let codepage = match id {
+            0 | 26 => "437",
+            5 | 25 | 27 => "866",
+            17 | 18 => "850",
+            19 | 28 => "865",
+            32 | 34 => return Some(Font::new(PETSCII_UNSHIFTED, 8, 8, 256)),
+            33 | 35 => return Some(Font::new(PETSCII_SHIFTED, 8, 8, 256)),
+            36 => return Some(Font::new(ATASCII_F08, 8, 8, 256)),
+            37 => return "Amiga P0T-NOoDLE".parse().ok(),
+            38 => return "Amiga mOsOul".parse().ok(),
+            39 => return "Amiga MicroKnight+".parse().ok(),
+            40 => return "Amiga Topaz 2+".parse().ok(),
+            41 => return "Amiga MicroKnight".parse().ok(),
+            42 => return "Amiga Topaz 2".parse().ok(),
+            _ => return None,
+        };
+        let mode = match height {
+            8 => "VGA50",
+            14 => "EGA",
+            19 => "VGA25G",
+            _ => "VGA",
+        };
+        format!("IBM {} {}", mode, codepage).parse().ok()
+    }
+
+    pub fn resized(&self, width: usize, height: usize) -> Font {
+        let bitmasks = self
+            .bitmasks
+            .iter()
+            .map(|glyph| {
+                let mut resized = Vec::with_capacity(width * height);
+                for y in 0..height {
+                    for x in 0..width {
+                        let index =
+                            (y * self.height / height) * self.width + x * self.width / width;
+                        resized.push(glyph.get(index).copied().unwrap_or(false));
+                    }
+                }
+                resized
+            })
+            .collect();
+        Font {
+            width,
+            height,
+            length: self.length,
+            bitmasks,
+        }
+    }
+
+    pub fn as_bitmask_file<P: AsRef<Path>>(&self, file: P) -> Result<()> {
+        let writer = File::create(&file).with_path(&file)?;
+        self.write_bitmask(BufWriter::new(writer)).with_path(&file)
+    }
+
+    pub fn write_bitmask<W: Write>(&self, writer: W) -> Result<()> {
+        write_bytes(&self.as_bitmask_bytes(), writer)
+    }
+
+    pub fn as_bitmask_bytes(&self) -> Vec<u8> {
         let bytes_len =
             (self.width as f32 * self.height as f32 * self.length as f32 / 8.0).ceil() as usize;
         let bits_len = bytes_len * 8;
@@ -218,14 +344,13 @@ impl Font {
             });
             bytes.push(byte);
         }
-        write_bytes_to_file(&bytes, file)?;
-        Ok(())
+        bytes
     }
 }
 
 impl FromStr for Font {
-    type Err = Box<dyn Error>;
-    fn from_str(string: &str) -> Result<Font, Box<dyn Error>> {
+    type Err = Error;
+    fn from_str(string: &str) -> Result<Font> {
         match string {
             "IBM VGA" => Ok(Font::new(CP437_F16, 8, 16, 256)),
             "IBM VGA50" => Ok(Font::new(CP437_F08, 8, 8, 256)),
@@ -377,7 +502,8 @@ impl FromStr for Font {
             "Amiga mOsOul" => Ok(Font::new(MO_SOUL_F16, 8, 16, 256)),
             "C64 PETSCII unshifted" => Ok(Font::new(PETSCII_UNSHIFTED, 8, 8, 256)),
             "C64 PETSCII shifted" => Ok(Font::new(PETSCII_SHIFTED, 8, 8, 256)),
-            _ => Err(Box::new(FontError::FontNotFound)),
+            "Atari ATASCII" => Ok(Font::new(ATASCII_F08, 8, 8, 256)),
+            _ => Err(FontError::FontNotFound.into()),
         }
     }
 }
@@ -387,3 +513,91 @@ impl Default for Font {
         Font::new(CP437_F16, 8, 16, 256)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bitmasks(font: Option<Font>) -> Vec<Vec<bool>> {
+        font.expect("font is embedded").bitmasks
+    }
+
+    #[test]
+    fn syncterm_amiga_ids() {
+        let font = |name: &str| bitmasks(name.parse().ok());
+        assert_eq!(
+            bitmasks(Font::from_syncterm_id(39, 16)),
+            font("Amiga MicroKnight+")
+        );
+        assert_eq!(
+            bitmasks(Font::from_syncterm_id(40, 16)),
+            font("Amiga Topaz 2+")
+        );
+        assert_eq!(
+            bitmasks(Font::from_syncterm_id(41, 16)),
+            font("Amiga MicroKnight")
+        );
+        assert_eq!(
+            bitmasks(Font::from_syncterm_id(42, 16)),
+            font("Amiga Topaz 2")
+        );
+        assert_ne!(font("Amiga MicroKnight+"), font("Amiga MicroKnight"));
+        assert_ne!(font("Amiga Topaz 2+"), font("Amiga Topaz 2"));
+        assert!(Font::from_syncterm_id(43, 16).is_none());
+    }
+
+    #[test]
+    fn bitmask_and_png_readers_round_trip() {
+        let font = Font::default();
+        let mut bitmask = Vec::new();
+        font.write_bitmask(&mut bitmask).unwrap();
+        assert_eq!(bitmask.len(), font.height * font.length);
+        let read = Font::from_bitmask_reader(&bitmask[..], 8, None, 256).unwrap();
+        assert_eq!(read.height, font.height);
+        assert_eq!(read.bitmasks, font.bitmasks);
+        let error = Font::from_bitmask_reader(&bitmask[..100], 8, Some(16), 256)
+            .err()
+            .unwrap();
+        assert!(matches!(
+            error,
+            Error::Font {
+                kind: FontError::FileNotCorrectSize,
+                ..
+            }
+        ));
+        let mut png = Vec::new();
+        font.write_png(&mut png, 16).unwrap();
+        let read = Font::from_png_reader(&png[..], 8, Some(font.height), 256).unwrap();
+        assert_eq!(read.bitmasks, font.bitmasks);
+    }
+
+    #[test]
+    fn xbin_reader() {
+        let font = Font::default();
+        let mut xbin = b"XBIN\x1a\x50\x00\x19\x00".to_vec();
+        xbin.push(font.height as u8);
+        xbin.push(0b10);
+        xbin.extend(font.as_bitmask_bytes());
+        let read = Font::from_xbin_reader(&xbin[..]).unwrap();
+        assert_eq!(read.bitmasks, font.bitmasks);
+        xbin[10] = 0;
+        let error = Font::from_xbin_reader(&xbin[..]).err().unwrap();
+        assert_eq!((error.field(), error.offset()), (Some("flags"), Some(10)));
+        xbin[10] = 0b10;
+        xbin.truncate(100);
+        let error = Font::from_xbin_reader(&xbin[..]).err().unwrap();
+        assert_eq!((error.field(), error.offset()), (Some("font"), Some(11)));
+    }
+
+    #[test]
+    fn sauce_reader_selects_font() {
+        let mut sauce = Sauce::new();
+        sauce.font_name = "Amiga Topaz 2".to_string();
+        let mut bytes = b"art".to_vec();
+        sauce.add_to_bytes(&mut bytes).unwrap();
+        let font = Font::from_sauce_reader(&mut std::io::Cursor::new(bytes)).unwrap();
+        assert_eq!(font.bitmasks, bitmasks("Amiga Topaz 2".parse().ok()));
+        let font = Font::from_sauce_reader(&mut std::io::Cursor::new(b"art".to_vec())).unwrap();
+        assert_eq!(font.bitmasks, Font::default().bitmasks);
+    }
+}
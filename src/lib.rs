@@ -1,6 +1,17 @@
+pub mod ansi;
+pub mod archive;
 pub mod ascii;
+pub mod atascii;
+pub mod bbs;
 pub mod bytes;
+pub mod canvas;
+pub mod detect;
+mod error;
 pub mod font;
+pub mod pack;
 pub mod render;
 pub mod sauce;
 pub mod string;
+pub mod tracker;
+
+pub use error::{Error, Result};
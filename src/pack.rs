@@ -0,0 +1,359 @@
+use crate::archive::ArchiveError;
+use crate::bytes::read_file_to_bytes;
+use crate::error::{Context, Result};
+use crate::font::Font;
+use crate::render::{captioned_contact_sheet, render_bytes, Image, SheetEntry};
+use crate::sauce::Sauce;
+use crate::string::CP437String;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufWriter, Seek, Write};
+use std::path::Path;
+use zip::write::{FileOptions, ZipWriter};
+use zip::CompressionMethod;
+
+pub static DIZ_NAME: &str = "FILE_ID.DIZ";
+pub static INDEX_NAME: &str = "NEWS";
+pub static SHEET_NAME: &str = "SHEET.PNG";
+static DIZ_WIDTH: usize = 45;
+static DIZ_LINES: usize = 10;
+static SHEET_FONT: &str = "IBM VGA50";
+static SHEET_COLUMNS: usize = 4;
+static SHEET_CELL_WIDTH: usize = 160;
+static SHEET_CELL_HEIGHT: usize = 200;
+
+pub struct PackFile {
+    pub name: String,
+    pub bytes: Vec<u8>,
+    pub sauce: Option<Sauce>,
+}
+
+impl PackFile {
+    pub fn from_bytes(name: &str, bytes: Vec<u8>) -> Result<PackFile> {
+        let sauce = Sauce::from_bytes(&bytes).with_path(name)?;
+        Ok(PackFile {
+            name: name.to_string(),
+            bytes,
+            sauce,
+        })
+    }
+
+    pub fn from_file<P: AsRef<Path>>(file: P) -> Result<PackFile> {
+        let bytes = read_file_to_bytes(&file)?;
+        let path = file.as_ref().display().to_string();
+        let name = match file.as_ref().file_name() {
+            Some(name) => name.to_string_lossy().to_string(),
+            None => path.clone(),
+        };
+        PackFile::from_bytes(&name, bytes).map(|mut pack_file| {
+            if let Some(sauce) = &mut pack_file.sauce {
+                sauce.file = Some(path);
+            }
+            pack_file
+        })
+    }
+
+    pub fn title(&self) -> &str {
+        self.sauce.as_ref().map_or("", |sauce| sauce.title.as_str())
+    }
+
+    pub fn author(&self) -> &str {
+        self.sauce
+            .as_ref()
+            .map_or("", |sauce| sauce.author.as_str())
+    }
+
+    pub fn font_name(&self) -> &str {
+        self.sauce
+            .as_ref()
+            .map_or("", |sauce| sauce.font_name.as_str())
+    }
+
+    pub fn caption(&self) -> Vec<String> {
+        let title = match self.title() {
+            "" => self.name.as_str(),
+            title => title,
+        };
+        match self.author() {
+            "" => vec![title.to_string()],
+            author => vec![title.to_string(), author.to_string()],
+        }
+    }
+
+    pub fn dimensions(&self) -> Option<(usize, usize)> {
+        let sauce = self.sauce.as_ref()?;
+        sauce
+            .character_dimensions()
+            .or_else(|| sauce.pixel_dimensions())
+            .filter(|&(width, height)| width > 0 || height > 0)
+    }
+}
+
+pub struct Pack {
+    pub name: String,
+    pub files: Vec<PackFile>,
+}
+
+fn truncate(string: &str, length: usize) -> String {
+    string.chars().take(length).collect()
+}
+
+fn center(string: &str, width: usize) -> String {
+    let string = truncate(string, width);
+    let padding = (width - string.chars().count()) / 2;
+    format!("{}{}", " ".repeat(padding), string)
+}
+
+fn join_lines(lines: &[String]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for line in lines {
+        bytes.extend(line.trim_end().to_string().as_cp437_bytes());
+        bytes.extend(b"\r\n");
+    }
+    bytes
+}
+
+impl Pack {
+    pub fn new(name: &str) -> Pack {
+        Pack {
+            name: name.to_string(),
+            files: Vec::new(),
+        }
+    }
+
+    pub fn add_file<P: AsRef<Path>>(&mut self, file: P) -> Result<()> {
+        let pack_file = PackFile::from_file(&file)?;
+        let name = pack_file.name.to_ascii_uppercase();
+        let reserved = [DIZ_NAME, INDEX_NAME, SHEET_NAME];
+        let duplicate = reserved.contains(&name.as_str())
+            || self
+                .files
+                .iter()
+                .any(|existing| existing.name.to_ascii_uppercase() == name);
+        if duplicate {
+            return Err(ArchiveError::DuplicateEntry).with_path(file);
+        }
+        self.files.push(pack_file);
+        Ok(())
+    }
+
+    pub fn file_id_diz(&self) -> Vec<u8> {
+        let mut lines = vec![
+            center(&self.name, DIZ_WIDTH),
+            center(&format!("{} files", self.files.len()), DIZ_WIDTH),
+            String::new(),
+        ];
+        let room = DIZ_LINES - lines.len();
+        for (i, file) in self.files.iter().enumerate() {
+            if i + 1 == room && self.files.len() > room {
+                lines.push(format!("...and {} more", self.files.len() - i));
+                break;
+            }
+            let line = match (file.title(), file.author()) {
+                ("", _) => file.name.clone(),
+                (title, "") => title.to_string(),
+                (title, author) => format!("{} by {}", title, author),
+            };
+            lines.push(truncate(&line, DIZ_WIDTH));
+        }
+        join_lines(&lines)
+    }
+
+    pub fn index(&self) -> Vec<u8> {
+        let headers = ["File", "Title", "Author", "Size", "Font"];
+        let rows: Vec<[String; 5]> = self
+            .files
+            .iter()
+            .map(|file| {
+                [
+                    file.name.clone(),
+                    file.title().to_string(),
+                    file.author().to_string(),
+                    match file.dimensions() {
+                        Some((width, height)) => format!("{}x{}", width, height),
+                        None => String::new(),
+                    },
+                    file.font_name().to_string(),
+                ]
+            })
+            .collect();
+        let mut widths = headers.map(|header| header.len());
+        for row in rows.iter() {
+            for (width, column) in widths.iter_mut().zip(row.iter()) {
+                *width = (*width).max(column.chars().count());
+            }
+        }
+        let format_row = |columns: &[String]| {
+            columns
+                .iter()
+                .zip(widths.iter())
+                .map(|(column, width)| format!("{:width$}", column, width = width))
+                .collect::<Vec<String>>()
+                .join("  ")
+        };
+        let mut lines = vec![
+            self.name.clone(),
+            "═".repeat(self.name.chars().count()),
+            String::new(),
+            format_row(&headers.map(String::from)),
+            format_row(&widths.map(|width| "─".repeat(width))),
+        ];
+        for row in rows.iter() {
+            lines.push(format_row(row));
+        }
+        join_lines(&lines)
+    }
+
+    pub fn contact_sheet(&self) -> Option<Image> {
+        let entries: Vec<SheetEntry> = self
+            .files
+            .iter()
+            .filter_map(|file| {
+                let image = render_bytes(&file.bytes, Some(&file.name))?;
+                Some(SheetEntry {
+                    image,
+                    caption: file.caption(),
+                })
+            })
+            .collect();
+        if entries.is_empty() {
+            return None;
+        }
+        let font = SHEET_FONT.parse::<Font>().ok()?;
+        Some(captioned_contact_sheet(
+            &entries,
+            SHEET_COLUMNS,
+            SHEET_CELL_WIDTH,
+            SHEET_CELL_HEIGHT,
+            &font,
+        ))
+    }
+
+    pub fn write_zip<W: Write + Seek>(&self, writer: W) -> Result<()> {
+        let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+        let mut zip = ZipWriter::new(writer);
+        let file_id_diz = self.file_id_diz();
+        zip.start_file(DIZ_NAME, options)?;
+        zip.write_all(&file_id_diz)?;
+        zip.start_file(INDEX_NAME, options)?;
+        zip.write_all(&self.index())?;
+        if let Some(sheet) = self.contact_sheet() {
+            let mut png = Vec::new();
+            sheet.write_png(&mut png)?;
+            zip.start_file(SHEET_NAME, options)?;
+            zip.write_all(&png)?;
+        }
+        let mut names: HashSet<String> = [DIZ_NAME, INDEX_NAME, SHEET_NAME]
+            .iter()
+            .map(|name| name.to_string())
+            .collect();
+        for file in self.files.iter() {
+            if !names.insert(file.name.to_ascii_uppercase()) {
+                return Err(ArchiveError::DuplicateEntry).with_path(&file.name);
+            }
+            zip.start_file(file.name.as_str(), options)
+                .with_path(&file.name)?;
+            zip.write_all(&file.bytes).with_path(&file.name)?;
+        }
+        zip.set_raw_comment(file_id_diz);
+        zip.finish()?;
+        Ok(())
+    }
+
+    pub fn write_zip_file<P: AsRef<Path>>(&self, file: P) -> Result<()> {
+        let writer = File::create(&file).with_path(&file)?;
+        self.write_zip(BufWriter::new(writer)).with_path(&file)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Error;
+    use std::io::{Cursor, Read};
+    use zip::ZipArchive;
+
+    fn pack_file(name: &str, title: &str) -> PackFile {
+        let mut bytes = b"\x1b[1;31mHello".to_vec();
+        let mut sauce = Sauce::new();
+        sauce.title = title.to_string();
+        sauce.author = "Artist".to_string();
+        sauce.add_to_bytes(&mut bytes).unwrap();
+        PackFile::from_bytes(name, bytes).unwrap()
+    }
+
+    #[test]
+    fn file_id_diz_lists_files() {
+        let mut pack = Pack::new("Test Pack");
+        pack.files.push(pack_file("ONE.ANS", "One"));
+        pack.files.push(pack_file("TWO.ANS", ""));
+        let diz = String::from_utf8(pack.file_id_diz()).unwrap();
+        let lines: Vec<&str> = diz.lines().collect();
+        assert_eq!(lines[0].trim(), "Test Pack");
+        assert_eq!(lines[1].trim(), "2 files");
+        assert_eq!(lines[3], "One by Artist");
+        assert_eq!(lines[4], "TWO.ANS");
+    }
+
+    #[test]
+    fn file_id_diz_is_truncated() {
+        let mut pack = Pack::new("Big Pack");
+        for i in 0..20 {
+            pack.files.push(pack_file(&format!("{:02}.ANS", i), ""));
+        }
+        let diz = String::from_utf8(pack.file_id_diz()).unwrap();
+        assert_eq!(diz.lines().count(), DIZ_LINES);
+        assert_eq!(diz.lines().last(), Some("...and 14 more"));
+    }
+
+    #[test]
+    fn zip_contains_pack_files() {
+        let mut pack = Pack::new("Zip Pack");
+        pack.files.push(pack_file("ONE.ANS", "One"));
+        let mut zip = Vec::new();
+        pack.write_zip(Cursor::new(&mut zip)).unwrap();
+        let mut archive = ZipArchive::new(Cursor::new(zip)).unwrap();
+        let names: Vec<&str> = archive.file_names().collect();
+        for name in [DIZ_NAME, INDEX_NAME, SHEET_NAME, "ONE.ANS"].iter() {
+            assert!(names.contains(name));
+        }
+        let mut bytes = Vec::new();
+        archive
+            .by_name("ONE.ANS")
+            .unwrap()
+            .read_to_end(&mut bytes)
+            .unwrap();
+        assert_eq!(bytes, pack.files[0].bytes);
+    }
+
+    #[test]
+    fn duplicate_names_are_rejected() {
+        let mut pack = Pack::new("Duplicates");
+        pack.files.push(pack_file("ONE.ANS", "One"));
+        pack.files.push(pack_file("one.ans", "Another one"));
+        let error = pack.write_zip(Cursor::new(Vec::new())).unwrap_err();
+        assert!(matches!(
+            error,
+            Error::Archive {
+                kind: ArchiveError::DuplicateEntry,
+                ..
+            }
+        ));
+        let directory = std::env::temp_dir().join(format!("tmode-pack-{}", std::process::id()));
+        let files = [directory.join("a/ART.ANS"), directory.join("b/ART.ANS")];
+        for file in files.iter() {
+            std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+            std::fs::write(file, b"art").unwrap();
+        }
+        let mut pack = Pack::new("Duplicates");
+        pack.add_file(&files[0]).unwrap();
+        assert!(pack.add_file(files[1].to_str().unwrap()).is_err());
+        assert_eq!(pack.files.len(), 1);
+        let output = directory.join("DUPES.ZIP");
+        pack.write_zip_file(&output).unwrap();
+        let bytes = std::fs::read(&output).unwrap();
+        let mut zip = ZipArchive::new(Cursor::new(bytes)).unwrap();
+        assert!(zip.by_name("ART.ANS").is_ok());
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,156 @@
+use crate::canvas::Canvas;
+use crate::error::{Context, Result};
+use crate::font::Font;
+use crate::render::{Image, RenderError, BLINK_PERIOD_MS, VGA_PALETTE};
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, Frame, RgbaImage};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+pub struct Animation {
+    pub frames: Vec<Image>,
+    pub delay_ms: usize,
+}
+
+impl Animation {
+    pub fn from_canvas(canvas: &Canvas, font: &Font, cursor: Option<(usize, usize)>) -> Animation {
+        let mut visible = Image::from_canvas(canvas, font);
+        if let Some((x, y)) = cursor {
+            let fg = match canvas.get(x, y) {
+                Some(cell) => cell.fg,
+                None => 7,
+            };
+            let size = (font.height / 8).max(1);
+            visible.fill_rect(
+                x * font.width,
+                (y + 1) * font.height - size - 1,
+                font.width,
+                size,
+                &VGA_PALETTE[fg as usize],
+            );
+        }
+        let mut hidden = canvas.clone();
+        for cell in hidden.cells.iter_mut().filter(|cell| cell.blink) {
+            cell.fg = cell.bg;
+        }
+        Animation {
+            frames: vec![visible, Image::from_canvas(&hidden, font)],
+            delay_ms: BLINK_PERIOD_MS / 2,
+        }
+    }
+
+    pub fn as_apng<P: AsRef<Path>>(&self, file: P) -> Result<()> {
+        let writer = File::create(&file).with_path(&file)?;
+        self.write_apng(BufWriter::new(writer)).with_path(&file)
+    }
+
+    pub fn write_apng<W: Write>(&self, writer: W) -> Result<()> {
+        let (width, height) = match self.frames.first() {
+            Some(frame) => (frame.width, frame.height),
+            None => (0, 0),
+        };
+        let mut encoder = png::Encoder::new(writer, width as u32, height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_compression(png::Compression::Best);
+        encoder.set_animated(self.frames.len() as u32, 0)?;
+        encoder.set_frame_delay(self.delay_ms as u16, 1000)?;
+        let mut writer = encoder.write_header()?;
+        for frame in self.frames.iter() {
+            writer.write_image_data(&frame.data)?;
+        }
+        writer.finish()?;
+        Ok(())
+    }
+
+    pub fn as_gif<P: AsRef<Path>>(&self, file: P) -> Result<()> {
+        let writer = File::create(&file).with_path(&file)?;
+        self.write_gif(BufWriter::new(writer)).with_path(&file)
+    }
+
+    pub fn write_gif<W: Write>(&self, writer: W) -> Result<()> {
+        let mut encoder = GifEncoder::new(writer);
+        encoder.set_repeat(Repeat::Infinite)?;
+        let delay = Delay::from_numer_denom_ms(self.delay_ms as u32, 1);
+        for frame in self.frames.iter() {
+            let rgba: Vec<u8> = frame
+                .data
+                .chunks(3)
+                .flat_map(|pixel| [pixel[0], pixel[1], pixel[2], 0xff])
+                .collect();
+            let buffer = RgbaImage::from_raw(frame.width as u32, frame.height as u32, rgba).ok_or(
+                RenderError::FrameSize {
+                    width: frame.width,
+                    height: frame.height,
+                },
+            )?;
+            encoder.encode_frame(Frame::from_parts(buffer, 0, 0, delay))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::Cell;
+    use crate::Error;
+
+    fn blinking_canvas() -> Canvas {
+        let mut canvas = Canvas::new(2, 1);
+        let cell = Cell {
+            code: b'A',
+            fg: 15,
+            bg: 1,
+            blink: true,
+            font: None,
+        };
+        canvas.set(0, 0, cell);
+        canvas
+    }
+
+    #[test]
+    fn blink_frames() {
+        let font = Font::default();
+        let animation = Animation::from_canvas(&blinking_canvas(), &font, None);
+        assert_eq!(animation.frames.len(), 2);
+        assert_eq!(animation.delay_ms, BLINK_PERIOD_MS / 2);
+        let hidden = &animation.frames[1];
+        let cell = &hidden.data[..font.width * 3];
+        assert!(cell.chunks(3).all(|pixel| pixel == VGA_PALETTE[1]));
+        assert_ne!(animation.frames[0].data, hidden.data);
+    }
+
+    #[test]
+    fn gif_and_apng_encode() {
+        let animation = Animation::from_canvas(&blinking_canvas(), &Font::default(), Some((1, 0)));
+        let mut gif = Vec::new();
+        animation.write_gif(&mut gif).unwrap();
+        assert!(gif.starts_with(b"GIF89a"));
+        let mut apng = Vec::new();
+        animation.write_apng(&mut apng).unwrap();
+        assert!(apng.windows(4).any(|window| window == b"acTL"));
+    }
+
+    #[test]
+    fn mismatched_frame_is_an_error() {
+        let mut frame = Image::new(2, 2);
+        frame.data.truncate(3);
+        let animation = Animation {
+            frames: vec![frame],
+            delay_ms: 100,
+        };
+        let error = animation.write_gif(Vec::new()).unwrap_err();
+        assert!(matches!(
+            error,
+            Error::Render {
+                kind: RenderError::FrameSize {
+                    width: 2,
+                    height: 2
+                },
+                ..
+            }
+        ));
+    }
+}
@@ -0,0 +1,175 @@
+use crate::error::Result;
+use crate::render::RenderError;
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+
+static MAX_PALETTE: usize = 256;
+
+#[derive(Clone, Copy)]
+pub enum PngColor {
+    Rgb,
+    Indexed,
+    Rgba([u8; 3]),
+    Auto,
+}
+
+pub(crate) struct PngFormat {
+    color: png::ColorType,
+    depth: png::BitDepth,
+    palette: Vec<[u8; 3]>,
+    indices: HashMap<[u8; 3], u8>,
+    transparent: [u8; 3],
+}
+
+impl PngFormat {
+    pub(crate) fn new(color: PngColor, colors: &[[u8; 3]]) -> Result<PngFormat> {
+        let mut format = PngFormat {
+            color: png::ColorType::Rgb,
+            depth: png::BitDepth::Eight,
+            palette: Vec::new(),
+            indices: HashMap::new(),
+            transparent: [0; 3],
+        };
+        match color {
+            PngColor::Rgba(transparent) => {
+                format.color = png::ColorType::Rgba;
+                format.transparent = transparent;
+            }
+            PngColor::Indexed | PngColor::Auto if colors.len() <= MAX_PALETTE => {
+                format.color = png::ColorType::Indexed;
+                format.depth = match colors.len() {
+                    0..=2 => png::BitDepth::One,
+                    3..=4 => png::BitDepth::Two,
+                    5..=16 => png::BitDepth::Four,
+                    _ => png::BitDepth::Eight,
+                };
+                format.palette = colors.to_vec();
+                for (i, rgb) in colors.iter().enumerate() {
+                    format.indices.insert(*rgb, i as u8);
+                }
+            }
+            PngColor::Indexed => {
+                return Err(RenderError::TooManyColors {
+                    maximum: MAX_PALETTE,
+                }
+                .into())
+            }
+            _ => {}
+        }
+        Ok(format)
+    }
+
+    pub(crate) fn encoder<W: Write>(
+        &self,
+        writer: W,
+        width: usize,
+        height: usize,
+    ) -> png::Encoder<'static, W> {
+        let mut encoder = png::Encoder::new(writer, width as u32, height as u32);
+        encoder.set_color(self.color);
+        encoder.set_depth(self.depth);
+        encoder.set_compression(png::Compression::Best);
+        if self.color == png::ColorType::Indexed {
+            encoder.set_palette(self.palette.concat());
+        }
+        encoder
+    }
+
+    pub(crate) fn encode_row(&self, rgb: &[u8], background: Option<&[bool]>, row: &mut Vec<u8>) {
+        let pixels = rgb.chunks(3).map(|pixel| [pixel[0], pixel[1], pixel[2]]);
+        match self.color {
+            png::ColorType::Rgba => {
+                for (x, pixel) in pixels.enumerate() {
+                    let is_background = background.map_or(true, |background| background[x]);
+                    let alpha = if is_background && pixel == self.transparent {
+                        0
+                    } else {
+                        0xff
+                    };
+                    row.extend_from_slice(&pixel);
+                    row.push(alpha);
+                }
+            }
+            png::ColorType::Indexed => {
+                let bits = self.depth as usize;
+                let per_byte = 8 / bits;
+                let indices: Vec<u8> = pixels.map(|pixel| self.indices[&pixel]).collect();
+                for chunk in indices.chunks(per_byte) {
+                    let mut byte = 0;
+                    for (i, index) in chunk.iter().enumerate() {
+                        byte |= index << (8 - bits * (i + 1));
+                    }
+                    row.push(byte);
+                }
+            }
+            _ => row.extend_from_slice(rgb),
+        }
+    }
+}
+
+pub(crate) fn distinct_colors(rgb: &[u8]) -> Vec<[u8; 3]> {
+    let mut colors = Vec::new();
+    let mut seen = HashSet::new();
+    for pixel in rgb.chunks(3) {
+        let pixel = [pixel[0], pixel[1], pixel[2]];
+        if seen.insert(pixel) {
+            colors.push(pixel);
+            if colors.len() > MAX_PALETTE {
+                break;
+            }
+        }
+    }
+    colors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alpha(row: &[u8]) -> Vec<u8> {
+        row.chunks(4).map(|pixel| pixel[3]).collect()
+    }
+
+    #[test]
+    fn rgba_uses_background_mask() {
+        let format = PngFormat::new(PngColor::Rgba([0, 0, 0]), &[]).unwrap();
+        let rgb = [0, 0, 0, 0, 0, 0, 0xaa, 0, 0];
+        let mut row = Vec::new();
+        format.encode_row(&rgb, Some(&[true, false, true]), &mut row);
+        assert_eq!(alpha(&row), [0, 0xff, 0xff]);
+        row.clear();
+        format.encode_row(&rgb, None, &mut row);
+        assert_eq!(alpha(&row), [0, 0, 0xff]);
+    }
+
+    #[test]
+    fn indexed_packs_bits() {
+        let colors = distinct_colors(&[0, 0, 0, 0xff, 0xff, 0xff, 0, 0, 0]);
+        assert_eq!(colors, [[0, 0, 0], [0xff, 0xff, 0xff]]);
+        let format = PngFormat::new(PngColor::Auto, &colors).unwrap();
+        assert_eq!(format.depth, png::BitDepth::One);
+        let mut row = Vec::new();
+        format.encode_row(&[0, 0, 0, 0xff, 0xff, 0xff, 0, 0, 0], None, &mut row);
+        assert_eq!(row, [0b0100_0000]);
+    }
+
+    #[test]
+    fn indexed_rejects_large_palettes() {
+        let rgb: Vec<u8> = (0..300u32)
+            .flat_map(|i| [i as u8, (i >> 8) as u8, 0])
+            .collect();
+        let colors = distinct_colors(&rgb);
+        assert_eq!(colors.len(), MAX_PALETTE + 1);
+        assert!(matches!(
+            PngFormat::new(PngColor::Indexed, &colors),
+            Err(crate::Error::Render {
+                kind: RenderError::TooManyColors { maximum: 256 },
+                ..
+            })
+        ));
+        let format = PngFormat::new(PngColor::Auto, &colors).unwrap();
+        assert!(format.color == png::ColorType::Rgb);
+        let format = PngFormat::new(PngColor::Indexed, &colors[..MAX_PALETTE]).unwrap();
+        assert!(format.color == png::ColorType::Indexed);
+    }
+}
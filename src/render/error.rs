@@ -0,0 +1,38 @@
+use std::error::Error;
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum RenderError {
+    Decoding(png::DecodingError),
+    Encoding(png::EncodingError),
+    Gif(image::ImageError),
+    FrameSize { width: usize, height: usize },
+    TooManyColors { maximum: usize },
+}
+
+impl std::fmt::Display for RenderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            RenderError::Decoding(error) => write!(f, "PNG decoding failed: {}", error),
+            RenderError::Encoding(error) => write!(f, "PNG encoding failed: {}", error),
+            RenderError::Gif(error) => write!(f, "GIF encoding failed: {}", error),
+            RenderError::FrameSize { width, height } => {
+                write!(f, "Frame data does not match its {}x{} size", width, height)
+            }
+            RenderError::TooManyColors { maximum } => {
+                write!(f, "Indexed PNGs cannot hold more than {} colors", maximum)
+            }
+        }
+    }
+}
+
+impl Error for RenderError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RenderError::Decoding(error) => Some(error),
+            RenderError::Encoding(error) => Some(error),
+            RenderError::Gif(error) => Some(error),
+            RenderError::FrameSize { .. } | RenderError::TooManyColors { .. } => None,
+        }
+    }
+}
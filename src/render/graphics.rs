@@ -0,0 +1,224 @@
+use crate::error::Result;
+use crate::render::Image;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+static SIXEL_COLORS: usize = 256;
+static KITTY_CHUNK_SIZE: usize = 4096;
+
+fn quantize(rgb: &[u8; 3]) -> [u8; 3] {
+    let level = |value: u8| ((value as usize * 5 + 127) / 255 * 51) as u8;
+    [level(rgb[0]), level(rgb[1]), level(rgb[2])]
+}
+
+fn percent(value: u8) -> usize {
+    (value as usize * 100 + 127) / 255
+}
+
+pub fn image_to_sixel(image: &Image) -> Vec<u8> {
+    let mut pixels: Vec<[u8; 3]> = image
+        .data
+        .chunks(3)
+        .map(|pixel| [pixel[0], pixel[1], pixel[2]])
+        .collect();
+    let mut registers: HashMap<[u8; 3], usize> = HashMap::new();
+    for pixel in pixels.iter() {
+        let next = registers.len();
+        registers.entry(*pixel).or_insert(next);
+    }
+    if registers.len() > SIXEL_COLORS {
+        registers.clear();
+        for pixel in pixels.iter_mut() {
+            *pixel = quantize(pixel);
+            let next = registers.len();
+            registers.entry(*pixel).or_insert(next);
+        }
+    }
+    let mut palette: Vec<([u8; 3], usize)> = registers
+        .iter()
+        .map(|(rgb, register)| (*rgb, *register))
+        .collect();
+    palette.sort_by_key(|(_, register)| *register);
+    let mut output = String::new();
+    write!(output, "\x1bPq\"1;1;{};{}", image.width, image.height).unwrap();
+    for (rgb, register) in palette.iter() {
+        write!(
+            output,
+            "#{};2;{};{};{}",
+            register,
+            percent(rgb[0]),
+            percent(rgb[1]),
+            percent(rgb[2])
+        )
+        .unwrap();
+    }
+    for band in (0..image.height).step_by(6) {
+        let rows = (image.height - band).min(6);
+        let mut columns: Vec<HashMap<usize, u8>> = vec![HashMap::new(); image.width];
+        let mut used = Vec::new();
+        for (x, column) in columns.iter_mut().enumerate() {
+            for row in 0..rows {
+                let register = registers[&pixels[(band + row) * image.width + x]];
+                *column.entry(register).or_insert(0) |= 1 << row;
+                if !used.contains(&register) {
+                    used.push(register);
+                }
+            }
+        }
+        for (i, register) in used.iter().enumerate() {
+            if i > 0 {
+                output.push('$');
+            }
+            write!(output, "#{}", register).unwrap();
+            let mut run: Option<(u8, usize)> = None;
+            for column in columns.iter() {
+                let bits = column.get(register).copied().unwrap_or(0);
+                match run {
+                    Some((previous, count)) if previous == bits => run = Some((bits, count + 1)),
+                    _ => {
+                        if let Some((previous, count)) = run {
+                            write_sixel_run(&mut output, previous, count);
+                        }
+                        run = Some((bits, 1));
+                    }
+                }
+            }
+            if let Some((previous, count)) = run {
+                if previous != 0 {
+                    write_sixel_run(&mut output, previous, count);
+                }
+            }
+        }
+        output.push('-');
+    }
+    output.push_str("\x1b\\");
+    output.into_bytes()
+}
+
+fn write_sixel_run(output: &mut String, bits: u8, count: usize) {
+    let sixel = (0x3f + bits) as char;
+    match count {
+        1..=3 => {
+            for _ in 0..count {
+                output.push(sixel);
+            }
+        }
+        _ => write!(output, "!{}{}", count, sixel).unwrap(),
+    }
+}
+
+fn encode_png(image: &Image) -> Result<(usize, String)> {
+    let mut png = Vec::new();
+    image.write_png(&mut png)?;
+    Ok((png.len(), base64::encode(&png)))
+}
+
+pub fn image_to_kitty(image: &Image) -> Result<Vec<u8>> {
+    let (_, payload) = encode_png(image)?;
+    let chunks: Vec<&str> = payload
+        .as_bytes()
+        .chunks(KITTY_CHUNK_SIZE)
+        .map(|chunk| std::str::from_utf8(chunk).unwrap())
+        .collect();
+    let mut output = String::new();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = if i + 1 < chunks.len() { 1 } else { 0 };
+        if i == 0 {
+            write!(output, "\x1b_Gf=100,a=T,m={};{}\x1b\\", more, chunk).unwrap();
+        } else {
+            write!(output, "\x1b_Gm={};{}\x1b\\", more, chunk).unwrap();
+        }
+    }
+    Ok(output.into_bytes())
+}
+
+pub fn image_to_iterm2(image: &Image) -> Result<Vec<u8>> {
+    let (size, payload) = encode_png(image)?;
+    let output = format!(
+        "\x1b]1337;File=inline=1;size={};width={}px;height={}px;preserveAspectRatio=1:{}\x07",
+        size, image.width, image.height, payload
+    );
+    Ok(output.into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn noise(width: usize, height: usize) -> Image {
+        let mut image = Image::new(width, height);
+        let mut seed: u32 = 1;
+        for value in image.data.iter_mut() {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            *value = (seed >> 16) as u8;
+        }
+        image
+    }
+
+    #[test]
+    fn sixel_bands_and_runs() {
+        let mut image = Image::new(5, 2);
+        image.fill_rect(0, 0, 5, 2, &[0xff, 0, 0]);
+        image.fill_rect(4, 0, 1, 1, &[0, 0, 0xff]);
+        let sixel = String::from_utf8(image_to_sixel(&image)).unwrap();
+        assert_eq!(
+            sixel,
+            "\x1bPq\"1;1;5;2#0;2;100;0;0#1;2;0;0;100#0!4BA$#1!4?@-\x1b\\"
+        );
+    }
+
+    #[test]
+    fn sixel_quantizes_large_palettes() {
+        let sixel = String::from_utf8(image_to_sixel(&noise(32, 32))).unwrap();
+        let registers = sixel.matches(";2;").count();
+        assert!(registers > 0 && registers <= SIXEL_COLORS);
+        assert_eq!(sixel.matches('-').count(), 6);
+    }
+
+    #[test]
+    fn kitty_chunks() {
+        let image = noise(64, 64);
+        let mut png = Vec::new();
+        image.write_png(&mut png).unwrap();
+        let kitty = String::from_utf8(image_to_kitty(&image).unwrap()).unwrap();
+        let commands: Vec<&str> = kitty.split_terminator("\x1b\\").collect();
+        assert!(commands.len() > 2);
+        let mut payload = String::new();
+        for (i, command) in commands.iter().enumerate() {
+            let control = match i {
+                0 => "\x1b_Gf=100,a=T,m=1;",
+                _ if i + 1 == commands.len() => "\x1b_Gm=0;",
+                _ => "\x1b_Gm=1;",
+            };
+            assert!(command.starts_with(control));
+            let chunk = &command[control.len()..];
+            assert!(chunk.len() <= KITTY_CHUNK_SIZE);
+            if i + 1 < commands.len() {
+                assert_eq!(chunk.len(), KITTY_CHUNK_SIZE);
+            }
+            payload.push_str(chunk);
+        }
+        assert_eq!(base64::decode(&payload).unwrap(), png);
+    }
+
+    #[test]
+    fn kitty_single_chunk() {
+        let kitty = String::from_utf8(image_to_kitty(&Image::new(2, 2)).unwrap()).unwrap();
+        assert!(kitty.starts_with("\x1b_Gf=100,a=T,m=0;"));
+        assert_eq!(kitty.matches("\x1b_G").count(), 1);
+    }
+
+    #[test]
+    fn iterm2_header() {
+        let image = Image::new(16, 8);
+        let mut png = Vec::new();
+        image.write_png(&mut png).unwrap();
+        let iterm2 = String::from_utf8(image_to_iterm2(&image).unwrap()).unwrap();
+        let expected = format!(
+            "\x1b]1337;File=inline=1;size={};width=16px;height=8px;preserveAspectRatio=1:{}\x07",
+            png.len(),
+            base64::encode(&png)
+        );
+        assert_eq!(iterm2, expected);
+    }
+}
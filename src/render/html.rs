@@ -0,0 +1,228 @@
+use crate::bytes::write_bytes_to_file;
+use crate::canvas::{Canvas, Cell};
+use crate::error::Result;
+use crate::font::Font;
+use crate::render::{BLINK_PERIOD_MS, VGA_PALETTE};
+use crate::string::CP437Char;
+use std::fmt::Write;
+use std::path::Path;
+
+static SPRITE_COLUMNS: usize = 16;
+
+pub struct Html {
+    pub style: String,
+    pub body: String,
+}
+
+fn hex(rgb: &[u8; 3]) -> String {
+    format!("#{:02x}{:02x}{:02x}", rgb[0], rgb[1], rgb[2])
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn escape_code(code: u8) -> String {
+    match code {
+        0 | 255 => " ".to_string(),
+        code => escape(&char::from_cp437_byte(code).to_string()),
+    }
+}
+
+fn classes(cell: &Cell) -> String {
+    let blink = if cell.blink { " k" } else { "" };
+    format!("f{} b{}{}", cell.fg, cell.bg, blink)
+}
+
+fn palette_style() -> String {
+    let mut style = String::new();
+    for (i, rgb) in VGA_PALETTE.iter().enumerate() {
+        writeln!(style, ".tmode .f{} {{ color: {}; }}", i, hex(rgb)).unwrap();
+        writeln!(
+            style,
+            ".tmode .b{} {{ background-color: {}; }}",
+            i,
+            hex(rgb)
+        )
+        .unwrap();
+    }
+    writeln!(
+        style,
+        ".tmode .k {{ animation: tmode-blink {}ms step-end infinite; }}",
+        BLINK_PERIOD_MS
+    )
+    .unwrap();
+    writeln!(
+        style,
+        "@keyframes tmode-blink {{ 50% {{ color: transparent; }} }}"
+    )
+    .unwrap();
+    style
+}
+
+fn sprite_style(font: &Font) -> Result<String> {
+    let mut png = Vec::new();
+    font.write_png(&mut png, SPRITE_COLUMNS)?;
+    let url = format!("url(data:image/png;base64,{})", base64::encode(&png));
+    let mut style = String::new();
+    writeln!(
+        style,
+        ".tmode {{ font-size: {}px; line-height: {}px; }}",
+        font.height, font.height
+    )
+    .unwrap();
+    writeln!(
+        style,
+        ".tmode .g {{ display: inline-block; position: relative; width: {}px; height: {}px; \
+         vertical-align: top; -webkit-text-fill-color: transparent; }}",
+        font.width, font.height
+    )
+    .unwrap();
+    writeln!(
+        style,
+        ".tmode .g::before {{ content: \"\"; position: absolute; left: 0; top: 0; \
+         width: 100%; height: 100%; background-color: currentColor; \
+         mask-image: {}; mask-mode: luminance; -webkit-mask-image: {}; }}",
+        url, url
+    )
+    .unwrap();
+    for code in 0..font.length {
+        let x = (code % SPRITE_COLUMNS) * font.width;
+        let y = (code / SPRITE_COLUMNS) * font.height;
+        writeln!(
+            style,
+            ".tmode .c{}::before {{ mask-position: -{}px -{}px; -webkit-mask-position: -{}px -{}px; }}",
+            code, x, y, x, y
+        )
+        .unwrap();
+    }
+    Ok(style)
+}
+
+impl Html {
+    pub fn from_canvas(canvas: &Canvas, font: Option<&Font>) -> Result<Html> {
+        let mut style = String::from(".tmode { font-family: monospace; line-height: 1; }\n");
+        style.push_str(&palette_style());
+        if let Some(font) = font {
+            style.push_str(&sprite_style(font)?);
+        }
+        let mut body = String::from("<pre class=\"tmode\">");
+        for row in canvas.cells.chunks(canvas.width.max(1)) {
+            match font {
+                Some(_) => {
+                    for cell in row {
+                        write!(
+                            body,
+                            "<span class=\"{} g c{}\">{}</span>",
+                            classes(cell),
+                            cell.code,
+                            escape_code(cell.code)
+                        )
+                        .unwrap();
+                    }
+                }
+                None => {
+                    let mut start = 0;
+                    while start < row.len() {
+                        let class = classes(&row[start]);
+                        let mut end = start + 1;
+                        while end < row.len() && classes(&row[end]) == class {
+                            end += 1;
+                        }
+                        let text: String = row[start..end]
+                            .iter()
+                            .map(|cell| escape_code(cell.code))
+                            .collect();
+                        write!(body, "<span class=\"{}\">{}</span>", class, text).unwrap();
+                        start = end;
+                    }
+                }
+            }
+            body.push('\n');
+        }
+        body.push_str("</pre>");
+        Ok(Html { style, body })
+    }
+
+    pub fn as_document(&self, title: &str) -> String {
+        format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+             <style>\n{}</style>\n</head>\n<body>\n{}\n</body>\n</html>\n",
+            escape(title),
+            self.style,
+            self.body
+        )
+    }
+
+    pub fn as_html_file<P: AsRef<Path>>(&self, file: P, title: &str) -> Result<()> {
+        write_bytes_to_file(self.as_document(title).as_bytes(), file)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn canvas() -> Canvas {
+        let mut canvas = Canvas::new(4, 2);
+        for (x, code) in b"<&a".iter().enumerate() {
+            let cell = Cell {
+                code: *code,
+                ..Cell::default()
+            };
+            canvas.set(x, 0, cell);
+        }
+        let cell = Cell {
+            code: 0xdb,
+            fg: 14,
+            bg: 1,
+            blink: true,
+            font: None,
+        };
+        canvas.set(3, 0, cell);
+        canvas
+    }
+
+    #[test]
+    fn text_runs_are_grouped_and_escaped() {
+        let html = Html::from_canvas(&canvas(), None).unwrap();
+        assert_eq!(
+            html.body,
+            "<pre class=\"tmode\"><span class=\"f7 b0\">&lt;&amp;a</span>\
+             <span class=\"f14 b1 k\">\u{2588}</span>\n\
+             <span class=\"f7 b0\">    </span>\n</pre>"
+        );
+        assert!(html.style.contains(".tmode .f14 { color: #ffff55; }"));
+        assert!(html
+            .style
+            .contains(".tmode .b1 { background-color: #0000aa; }"));
+        assert!(html.style.contains("tmode-blink"));
+        assert!(!html.style.contains("mask-image"));
+    }
+
+    #[test]
+    fn font_sprites_use_one_span_per_cell() {
+        let font = Font::default();
+        let html = Html::from_canvas(&canvas(), Some(&font)).unwrap();
+        assert!(html
+            .body
+            .starts_with("<pre class=\"tmode\"><span class=\"f7 b0 g c60\">&lt;</span>"));
+        assert_eq!(html.body.matches("<span").count(), 8);
+        assert!(html.style.contains("url(data:image/png;base64,"));
+        let x = (0xdb % SPRITE_COLUMNS) * font.width;
+        let y = (0xdb / SPRITE_COLUMNS) * font.height;
+        let position = format!(".tmode .c219::before {{ mask-position: -{}px -{}px;", x, y);
+        assert!(html.style.contains(&position));
+    }
+
+    #[test]
+    fn document_escapes_title() {
+        let html = Html::from_canvas(&Canvas::new(1, 1), None).unwrap();
+        let document = html.as_document("a <b> & c");
+        assert!(document.starts_with("<!DOCTYPE html>"));
+        assert!(document.contains("<title>a &lt;b&gt; &amp; c</title>"));
+        assert!(document.contains(&html.body));
+    }
+}
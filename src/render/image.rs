@@ -1,13 +1,50 @@
+use crate::canvas::Canvas;
+use crate::error::{Context, Result};
 use crate::font::Font;
-use std::error::Error;
+use crate::render::encode::{distinct_colors, PngFormat};
+use crate::render::{PngColor, VGA_PALETTE};
+use std::collections::HashMap;
 use std::fs::File;
-use std::io::BufWriter;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::ops::Range;
+use std::path::Path;
+
+pub(crate) fn syncterm_fonts(canvas: &Canvas, font: &Font) -> HashMap<u8, Option<Font>> {
+    let mut fonts = HashMap::new();
+    for cell in canvas.cells.iter() {
+        if let Some(id) = cell.font {
+            fonts.entry(id).or_insert_with(|| {
+                Font::from_syncterm_id(id, font.height)
+                    .map(|selected| selected.resized(font.width, font.height))
+            });
+        }
+    }
+    fonts
+}
+
+fn area_weights(source: usize, target: usize) -> Vec<Vec<(usize, f64)>> {
+    let scale = source as f64 / target as f64;
+    (0..target)
+        .map(|i| {
+            let start = i as f64 * scale;
+            let end = start + scale;
+            (start.floor() as usize..(end.ceil() as usize).min(source))
+                .map(|j| {
+                    let coverage = end.min(j as f64 + 1.0) - start.max(j as f64);
+                    (j, coverage / scale)
+                })
+                .filter(|(_, weight)| *weight > 0.0)
+                .collect()
+        })
+        .collect()
+}
 
 pub struct Image {
     pub width: usize,
     pub height: usize,
     pub data: Vec<u8>,
     line_len: usize,
+    background: Option<Vec<bool>>,
 }
 
 impl Image {
@@ -17,12 +54,17 @@ impl Image {
             height,
             data: vec![0; width * height * 3],
             line_len: width * 3,
+            background: None,
         }
     }
 
-    pub fn from_file(file: &str) -> Result<Image, Box<dyn Error>> {
-        let file = File::open(file)?;
-        let decoder = png::Decoder::new(file);
+    pub fn from_file<P: AsRef<Path>>(file: P) -> Result<Image> {
+        let reader = File::open(&file).with_path(&file)?;
+        Image::from_reader(BufReader::new(reader)).with_path(&file)
+    }
+
+    pub fn from_reader<R: Read>(reader: R) -> Result<Image> {
+        let decoder = png::Decoder::new(reader);
         let mut reader = decoder.read_info()?;
         let mut data = vec![0; reader.output_buffer_size()];
         reader.next_frame(&mut data)?;
@@ -32,10 +74,113 @@ impl Image {
             height: info.height as usize,
             data,
             line_len: (info.width as usize) * 3,
+            background: None,
         };
         Ok(image)
     }
 
+    pub fn from_canvas(canvas: &Canvas, font: &Font) -> Image {
+        let fonts = syncterm_fonts(canvas, font);
+        Image::from_canvas_rows(canvas, font, &fonts, 0..canvas.height)
+    }
+
+    pub(crate) fn from_canvas_rows(
+        canvas: &Canvas,
+        font: &Font,
+        fonts: &HashMap<u8, Option<Font>>,
+        rows: Range<usize>,
+    ) -> Image {
+        let mut image = Image::new(canvas.width * font.width, rows.len() * font.height);
+        image.background = Some(vec![false; image.width * image.height]);
+        let cells = &canvas.cells[rows.start * canvas.width..rows.end * canvas.width];
+        for (i, cell) in cells.iter().enumerate() {
+            let cell_font = match cell.font.and_then(|id| fonts[&id].as_ref()) {
+                Some(selected) => selected,
+                None => font,
+            };
+            image.draw_font(
+                (i % canvas.width) * font.width,
+                (i / canvas.width) * font.height,
+                cell_font,
+                cell.code as usize,
+                &VGA_PALETTE[cell.fg as usize],
+                &VGA_PALETTE[cell.bg as usize],
+            );
+        }
+        image
+    }
+
+    pub fn thumbnail(&self, width: usize) -> Image {
+        let width = width.clamp(1, self.width.max(1));
+        let height = ((self.height * width + self.width / 2) / self.width.max(1)).max(1);
+        let columns = area_weights(self.width, width);
+        let rows = area_weights(self.height, height);
+        let mut horizontal = vec![0.0; width * self.height * 3];
+        for y in 0..self.height {
+            for (x, weights) in columns.iter().enumerate() {
+                let dest = (y * width + x) * 3;
+                for (source_x, weight) in weights.iter() {
+                    let source = y * self.line_len + source_x * 3;
+                    for channel in 0..3 {
+                        horizontal[dest + channel] += self.data[source + channel] as f64 * weight;
+                    }
+                }
+            }
+        }
+        let mut image = Image::new(width, height);
+        for (y, weights) in rows.iter().enumerate() {
+            for x in 0..width {
+                let mut sum = [0.0; 3];
+                for (source_y, weight) in weights.iter() {
+                    let source = (source_y * width + x) * 3;
+                    for (total, value) in sum.iter_mut().zip(&horizontal[source..source + 3]) {
+                        *total += value * weight;
+                    }
+                }
+                let i = (y * width + x) * 3;
+                for (value, total) in image.data[i..i + 3].iter_mut().zip(sum.iter()) {
+                    *value = total.round().clamp(0.0, 255.0) as u8;
+                }
+            }
+        }
+        image
+    }
+
+    pub fn draw_image(&mut self, x: usize, y: usize, image: &Image) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+        let width = image.width.min(self.width - x);
+        let height = image.height.min(self.height - y);
+        for row in 0..height {
+            let source = row * image.line_len;
+            let dest = (y + row) * self.line_len + x * 3;
+            self.data[dest..dest + width * 3]
+                .copy_from_slice(&image.data[source..source + width * 3]);
+            if let Some(background) = self.background.as_mut() {
+                let dest = (y + row) * self.width + x;
+                for (i, flag) in background[dest..dest + width].iter_mut().enumerate() {
+                    *flag = match &image.background {
+                        Some(source) => source[row * image.width + i],
+                        None => false,
+                    };
+                }
+            }
+        }
+    }
+
+    pub fn fill_rect(&mut self, x: usize, y: usize, width: usize, height: usize, rgb: &[u8; 3]) {
+        for row in y..(y + height).min(self.height) {
+            for column in x..(x + width).min(self.width) {
+                let i = row * self.line_len + column * 3;
+                self.data[i..i + 3].copy_from_slice(rgb);
+                if let Some(background) = self.background.as_mut() {
+                    background[row * self.width + column] = false;
+                }
+            }
+        }
+    }
+
     pub fn draw_font(
         &mut self,
         x: usize,
@@ -48,7 +193,14 @@ impl Image {
         let mut boolmask_index = 0;
         let mut i = (y * self.width + x) * 3;
         let line_len = self.line_len - font.width * 3;
-        for _ in 0..font.height {
+        for row in 0..font.height {
+            if let Some(background) = self.background.as_mut() {
+                let start = (y + row) * self.width + x;
+                let glyph = &font.bitmasks[code][row * font.width..(row + 1) * font.width];
+                for (flag, set) in background[start..start + font.width].iter_mut().zip(glyph) {
+                    *flag = !set;
+                }
+            }
             for _ in 0..font.width {
                 if font.bitmasks[code][boolmask_index] {
                     self.data[i] = fg[0];
@@ -66,15 +218,91 @@ impl Image {
         }
     }
 
-    pub fn as_png(&self, file: &str) -> Result<(), Box<dyn Error>> {
-        let file = File::create(file)?;
-        let buffer = BufWriter::new(file);
-        let mut encoder = png::Encoder::new(buffer, self.width as u32, self.height as u32);
-        encoder.set_color(png::ColorType::Rgb);
-        encoder.set_depth(png::BitDepth::Eight);
-        encoder.set_compression(png::Compression::Best);
+    pub(crate) fn row(&self, y: usize) -> &[u8] {
+        &self.data[y * self.line_len..(y + 1) * self.line_len]
+    }
+
+    pub(crate) fn background_row(&self, y: usize) -> Option<&[bool]> {
+        let background = self.background.as_ref()?;
+        Some(&background[y * self.width..(y + 1) * self.width])
+    }
+
+    pub fn as_png<P: AsRef<Path>>(&self, file: P) -> Result<()> {
+        self.as_png_with(file, PngColor::Rgb)
+    }
+
+    pub fn as_png_with<P: AsRef<Path>>(&self, file: P, color: PngColor) -> Result<()> {
+        let writer = File::create(&file).with_path(&file)?;
+        self.write_png_with(BufWriter::new(writer), color)
+            .with_path(&file)
+    }
+
+    pub fn write_png<W: Write>(&self, writer: W) -> Result<()> {
+        self.write_png_with(writer, PngColor::Rgb)
+    }
+
+    pub fn write_png_with<W: Write>(&self, writer: W, color: PngColor) -> Result<()> {
+        let format = PngFormat::new(color, &distinct_colors(&self.data))?;
+        let encoder = format.encoder(writer, self.width, self.height);
         let mut writer = encoder.write_header()?;
-        writer.write_image_data(&self.data)?;
+        let mut data = Vec::new();
+        for y in 0..self.height {
+            format.encode_row(self.row(y), self.background_row(y), &mut data);
+        }
+        writer.write_image_data(&data)?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::Cell;
+    use crate::render::encode::PngFormat;
+
+    #[test]
+    fn transparent_background_keeps_glyphs() {
+        let mut canvas = Canvas::new(2, 1);
+        let block = Cell {
+            code: 219,
+            fg: 0,
+            bg: 1,
+            ..Cell::default()
+        };
+        let space = Cell {
+            fg: 0,
+            bg: 0,
+            ..Cell::default()
+        };
+        canvas.set(0, 0, block);
+        canvas.set(1, 0, space);
+        let font = Font::default();
+        let image = Image::from_canvas(&canvas, &font);
+        let format = PngFormat::new(PngColor::Rgba(VGA_PALETTE[0]), &[]).unwrap();
+        for y in 0..image.height {
+            let mut row = Vec::new();
+            format.encode_row(image.row(y), image.background_row(y), &mut row);
+            let alpha: Vec<u8> = row.chunks(4).map(|pixel| pixel[3]).collect();
+            assert!(alpha[..font.width].iter().all(|alpha| *alpha == 0xff));
+            assert!(alpha[font.width..].iter().all(|alpha| *alpha == 0));
+        }
+    }
+
+    #[test]
+    fn thumbnail_averages_areas() {
+        let mut image = Image::new(4, 2);
+        image.fill_rect(0, 0, 2, 2, &[200, 100, 0]);
+        let thumbnail = image.thumbnail(2);
+        assert_eq!((thumbnail.width, thumbnail.height), (2, 1));
+        assert_eq!(thumbnail.data, [200, 100, 0, 0, 0, 0]);
+        let thumbnail = image.thumbnail(1);
+        assert_eq!(thumbnail.data, [100, 50, 0]);
+    }
+
+    #[test]
+    fn thumbnail_never_upscales() {
+        let image = Image::new(3, 5);
+        let thumbnail = image.thumbnail(10);
+        assert_eq!((thumbnail.width, thumbnail.height), (3, 5));
+    }
+}
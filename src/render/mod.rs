@@ -1,4 +1,52 @@
+mod animation;
 mod color;
+mod encode;
+mod error;
+mod graphics;
+mod html;
 mod image;
-pub use self::color::Color;
+mod sheet;
+mod stream;
+mod svg;
+mod terminal;
+pub use self::animation::Animation;
+pub use self::color::{Color, VGA_PALETTE};
+pub use self::encode::PngColor;
+pub use self::error::RenderError;
+pub use self::graphics::{image_to_iterm2, image_to_kitty, image_to_sixel};
+pub use self::html::Html;
 pub use self::image::Image;
+pub use self::sheet::{captioned_contact_sheet, SheetEntry};
+pub use self::stream::BandRenderer;
+pub use self::svg::{Svg, SvgGlyphs};
+pub use self::terminal::{canvas_to_terminal, image_to_terminal, TerminalColors};
+use crate::ansi::Ansi;
+use crate::atascii::Atascii;
+use crate::bbs::{MacroColor, PCBoard, Pipe, Wildcat, Wwiv};
+use crate::canvas::Canvas;
+use crate::detect::{detect, TextFormat};
+use crate::font::Font;
+use crate::sauce::Sauce;
+
+pub static BLINK_PERIOD_MS: usize = 457;
+
+pub fn canvas_from_bytes(bytes: &[u8], format: TextFormat) -> Canvas {
+    match format {
+        TextFormat::Ansi => Ansi::from_bytes(bytes).canvas,
+        TextFormat::Atascii => Atascii::from_bytes(bytes).canvas,
+        TextFormat::PCBoard => PCBoard::from_bytes(bytes),
+        TextFormat::Pipe => Pipe::from_bytes(bytes),
+        TextFormat::Wildcat => Wildcat::from_bytes(bytes),
+        TextFormat::Wwiv => Wwiv::from_bytes(bytes),
+    }
+}
+
+pub fn render_bytes(bytes: &[u8], file: Option<&str>) -> Option<Image> {
+    let format = detect(bytes, file)?.text_format?;
+    let canvas = canvas_from_bytes(bytes, format);
+    let font = match format {
+        TextFormat::Atascii => "Atari ATASCII".parse().ok()?,
+        _ => Font::from_sauce_record(&Sauce::from_bytes(bytes).ok().flatten()),
+    };
+    Some(Image::from_canvas(&canvas, &font))
+}
@@ -0,0 +1,118 @@
+use crate::font::Font;
+use crate::render::{Image, VGA_PALETTE};
+use crate::string::CP437String;
+
+static GAP: usize = 8;
+
+pub struct SheetEntry {
+    pub image: Image,
+    pub caption: Vec<String>,
+}
+
+fn fit(image: &Image, cell_width: usize, cell_height: usize) -> Image {
+    let thumbnail = image.thumbnail(cell_width.min(image.width));
+    let mut cell = Image::new(thumbnail.width, thumbnail.height.min(cell_height));
+    cell.draw_image(0, 0, &thumbnail);
+    cell
+}
+
+pub fn captioned_contact_sheet(
+    entries: &[SheetEntry],
+    columns: usize,
+    cell_width: usize,
+    cell_height: usize,
+    font: &Font,
+) -> Image {
+    let columns = columns.max(1).min(entries.len().max(1));
+    let rows = (entries.len() + columns - 1) / columns;
+    let caption_lines = entries
+        .iter()
+        .map(|entry| entry.caption.len())
+        .max()
+        .unwrap_or(0);
+    let caption_height = caption_lines * font.height;
+    let row_height = cell_height + caption_height + GAP;
+    let mut sheet = Image::new(
+        columns * (cell_width + GAP) + GAP,
+        rows * (row_height + GAP) + GAP,
+    );
+    let characters = cell_width / font.width.max(1);
+    for (i, entry) in entries.iter().enumerate() {
+        let cell = fit(&entry.image, cell_width, cell_height);
+        let left = GAP + (i % columns) * (cell_width + GAP);
+        let top = GAP + (i / columns) * (row_height + GAP);
+        sheet.draw_image(left + (cell_width - cell.width) / 2, top, &cell);
+        for (line, text) in entry.caption.iter().enumerate() {
+            let bytes = text.as_cp437_bytes();
+            let bytes = &bytes[..bytes.len().min(characters)];
+            let x = left + (cell_width - bytes.len() * font.width) / 2;
+            let y = top + cell_height + GAP + line * font.height;
+            for (column, code) in bytes.iter().enumerate() {
+                sheet.draw_font(
+                    x + column * font.width,
+                    y,
+                    font,
+                    *code as usize,
+                    &VGA_PALETTE[7],
+                    &VGA_PALETTE[0],
+                );
+            }
+        }
+    }
+    sheet
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pixel(image: &Image, x: usize, y: usize) -> &[u8] {
+        &image.row(y)[x * 3..x * 3 + 3]
+    }
+
+    #[test]
+    fn sheet_layout() {
+        let font = Font::default();
+        let white = |width: usize, height: usize| {
+            let mut image = Image::new(width, height);
+            image.fill_rect(0, 0, width, height, &[0xff, 0xff, 0xff]);
+            image
+        };
+        let entries: Vec<SheetEntry> = (0..3)
+            .map(|i| SheetEntry {
+                image: white(10, 20),
+                caption: vec![format!("{}", i)],
+            })
+            .collect();
+        let sheet = captioned_contact_sheet(&entries, 2, 30, 30, &font);
+        let row_height = 30 + font.height + GAP;
+        assert_eq!(sheet.width, 2 * (30 + GAP) + GAP);
+        assert_eq!(sheet.height, 2 * (row_height + GAP) + GAP);
+        for i in 0..entries.len() {
+            let left = GAP + (i % 2) * (30 + GAP);
+            let top = GAP + (i / 2) * (row_height + GAP);
+            for y in top..top + 20 {
+                assert_eq!(pixel(&sheet, left + 9, y), [0, 0, 0]);
+                assert!((left + 10..left + 20).all(|x| pixel(&sheet, x, y) == [0xff, 0xff, 0xff]));
+                assert_eq!(pixel(&sheet, left + 20, y), [0, 0, 0]);
+            }
+            let glyph = &font.bitmasks[b'0' as usize + i];
+            assert!(glyph.iter().any(|set| *set));
+            let x = left + (30 - font.width) / 2;
+            let y = top + 30 + GAP;
+            for row in 0..font.height {
+                for column in 0..font.width {
+                    let expected = if glyph[row * font.width + column] {
+                        VGA_PALETTE[7]
+                    } else {
+                        VGA_PALETTE[0]
+                    };
+                    assert_eq!(pixel(&sheet, x + column, y + row), expected);
+                }
+            }
+        }
+        let tall = fit(&white(40, 40), 20, 10);
+        assert_eq!((tall.width, tall.height), (20, 10));
+        assert!(tall.data.iter().all(|value| *value == 0xff));
+    }
+}
@@ -0,0 +1,181 @@
+use crate::canvas::Canvas;
+use crate::error::{Context, Result};
+use crate::font::Font;
+use crate::render::encode::PngFormat;
+use crate::render::image::syncterm_fonts;
+use crate::render::{Image, PngColor, VGA_PALETTE};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+pub struct BandRenderer<'a> {
+    canvas: &'a Canvas,
+    font: &'a Font,
+    fonts: HashMap<u8, Option<Font>>,
+}
+
+impl<'a> BandRenderer<'a> {
+    pub fn new(canvas: &'a Canvas, font: &'a Font) -> BandRenderer<'a> {
+        BandRenderer {
+            canvas,
+            font,
+            fonts: syncterm_fonts(canvas, font),
+        }
+    }
+
+    pub fn band(&self, y: usize) -> Image {
+        Image::from_canvas_rows(self.canvas, self.font, &self.fonts, y..y + 1)
+    }
+
+    pub fn write_png<W: Write>(
+        &self,
+        rows: Range<usize>,
+        writer: W,
+        color: PngColor,
+    ) -> Result<()> {
+        let width = self.canvas.width * self.font.width;
+        let height = rows.len() * self.font.height;
+        let format = PngFormat::new(color, &self.colors(rows.clone()))?;
+        let encoder = format.encoder(writer, width, height);
+        let mut writer = encoder.write_header()?;
+        let mut stream = writer.stream_writer()?;
+        let mut data = Vec::new();
+        for y in rows {
+            let band = self.band(y);
+            data.clear();
+            for row in 0..band.height {
+                format.encode_row(band.row(row), band.background_row(row), &mut data);
+            }
+            stream.write_all(&data)?;
+        }
+        stream.finish()?;
+        Ok(())
+    }
+
+    fn colors(&self, rows: Range<usize>) -> Vec<[u8; 3]> {
+        let width = self.canvas.width;
+        let mut used = [false; 16];
+        for cell in self.canvas.cells[rows.start * width..rows.end * width].iter() {
+            used[cell.fg as usize] = true;
+            used[cell.bg as usize] = true;
+        }
+        VGA_PALETTE
+            .iter()
+            .zip(used.iter())
+            .filter(|(_, used)| **used)
+            .map(|(rgb, _)| *rgb)
+            .collect()
+    }
+
+    pub fn as_png<P: AsRef<Path>>(&self, file: P, color: PngColor) -> Result<()> {
+        let writer = File::create(&file).with_path(&file)?;
+        self.write_png(0..self.canvas.height, BufWriter::new(writer), color)
+            .with_path(&file)
+    }
+
+    pub fn as_split_pngs<P: AsRef<Path>>(
+        &self,
+        file: P,
+        rows: usize,
+        color: PngColor,
+    ) -> Result<Vec<PathBuf>> {
+        let file = file.as_ref();
+        let stem = file.file_stem().unwrap_or_default().to_string_lossy();
+        let extension = file.extension().unwrap_or_default().to_string_lossy();
+        let rows = rows.max(1);
+        let mut files = Vec::new();
+        for (i, start) in (0..self.canvas.height).step_by(rows).enumerate() {
+            let name = match extension.as_ref() {
+                "" => format!("{}-{:03}", stem, i + 1),
+                extension => format!("{}-{:03}.{}", stem, i + 1, extension),
+            };
+            let part = file.with_file_name(name);
+            let writer = File::create(&part).with_path(&part)?;
+            let end = (start + rows).min(self.canvas.height);
+            self.write_png(start..end, BufWriter::new(writer), color)
+                .with_path(&part)?;
+            files.push(part);
+        }
+        Ok(files)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::Cell;
+
+    fn canvas() -> Canvas {
+        let mut canvas = Canvas::new(3, 5);
+        for (i, cell) in canvas.cells.iter_mut().enumerate() {
+            *cell = Cell {
+                code: b'A' + i as u8,
+                fg: (i % 16) as u8,
+                bg: (i % 3) as u8,
+                ..Cell::default()
+            };
+        }
+        canvas
+    }
+
+    fn decode(png: &[u8]) -> (png::OutputInfo, Vec<u8>) {
+        let decoder = png::Decoder::new(png);
+        let mut reader = decoder.read_info().unwrap();
+        let mut data = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut data).unwrap();
+        data.truncate(info.buffer_size());
+        (info, data)
+    }
+
+    #[test]
+    fn streamed_png_matches_whole_render() {
+        let canvas = canvas();
+        let font = Font::default();
+        let renderer = BandRenderer::new(&canvas, &font);
+        let mut png = Vec::new();
+        renderer
+            .write_png(0..canvas.height, &mut png, PngColor::Rgb)
+            .unwrap();
+        let (info, data) = decode(&png);
+        let image = Image::from_canvas(&canvas, &font);
+        assert_eq!(
+            (info.width as usize, info.height as usize),
+            (image.width, image.height)
+        );
+        assert_eq!(data, image.data);
+        let mut indexed = Vec::new();
+        renderer
+            .write_png(1..3, &mut indexed, PngColor::Auto)
+            .unwrap();
+        let (info, _) = decode(&indexed);
+        assert_eq!(info.color_type, png::ColorType::Indexed);
+        assert_eq!(info.height as usize, 2 * font.height);
+    }
+
+    #[test]
+    fn split_pngs_cover_every_row() {
+        let canvas = canvas();
+        let font = Font::default();
+        let renderer = BandRenderer::new(&canvas, &font);
+        let directory = std::env::temp_dir().join(format!("tmode-stream-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let files = renderer
+            .as_split_pngs(directory.join("art.png"), 2, PngColor::Rgb)
+            .unwrap();
+        let names: Vec<_> = files
+            .iter()
+            .map(|file| file.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names, ["art-001.png", "art-002.png", "art-003.png"]);
+        let mut data = Vec::new();
+        for (file, rows) in files.iter().zip([2, 2, 1].iter()) {
+            let (info, part) = decode(&std::fs::read(file).unwrap());
+            assert_eq!(info.height as usize, rows * font.height);
+            data.extend(part);
+        }
+        assert_eq!(data, Image::from_canvas(&canvas, &font).data);
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,369 @@
+use crate::bytes::write_bytes_to_file;
+use crate::canvas::{Canvas, Cell};
+use crate::error::Result;
+use crate::font::Font;
+use crate::render::image::syncterm_fonts;
+use crate::render::VGA_PALETTE;
+use crate::sauce::{AspectRatio, LetterSpacing};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::path::Path;
+
+pub enum SvgGlyphs {
+    Paths,
+    Symbols,
+}
+
+pub struct Svg {
+    pub width: usize,
+    pub height: usize,
+    pub display_height: usize,
+    pub body: String,
+}
+
+struct Glyph {
+    width: usize,
+    height: usize,
+    pixels: Vec<bool>,
+}
+
+impl Glyph {
+    fn new(font: &Font, code: usize, width: usize) -> Glyph {
+        let mut pixels = Vec::with_capacity(width * font.height);
+        for row in font.bitmasks[code].chunks(font.width) {
+            pixels.extend_from_slice(row);
+            for _ in font.width..width {
+                let repeat = (0xc0..=0xdf).contains(&code) && row[font.width - 1];
+                pixels.push(repeat);
+            }
+        }
+        Glyph {
+            width,
+            height: font.height,
+            pixels,
+        }
+    }
+
+    fn get(&self, x: isize, y: isize) -> bool {
+        x >= 0
+            && y >= 0
+            && (x as usize) < self.width
+            && (y as usize) < self.height
+            && self.pixels[y as usize * self.width + x as usize]
+    }
+
+    fn outline(&self) -> Vec<((isize, isize), String)> {
+        let mut edges: BTreeMap<(isize, isize), Vec<(isize, isize)>> = BTreeMap::new();
+        for py in 0..self.height as isize {
+            for px in 0..self.width as isize {
+                if !self.get(px, py) {
+                    continue;
+                }
+                if !self.get(px, py - 1) {
+                    edges.entry((px, py)).or_default().push((px + 1, py));
+                }
+                if !self.get(px + 1, py) {
+                    edges
+                        .entry((px + 1, py))
+                        .or_default()
+                        .push((px + 1, py + 1));
+                }
+                if !self.get(px, py + 1) {
+                    edges
+                        .entry((px + 1, py + 1))
+                        .or_default()
+                        .push((px, py + 1));
+                }
+                if !self.get(px - 1, py) {
+                    edges.entry((px, py + 1)).or_default().push((px, py));
+                }
+            }
+        }
+        let mut outline = Vec::new();
+        while let Some((&start, _)) = edges.iter().find(|(_, ends)| !ends.is_empty()) {
+            let mut path = String::new();
+            let mut point = start;
+            let mut direction = (0, 0);
+            let mut length = 0;
+            loop {
+                let next = edges.get_mut(&point).unwrap().pop().unwrap();
+                let step = ((next.0 - point.0).signum(), (next.1 - point.1).signum());
+                if step != direction && length != 0 {
+                    write_segment(&mut path, direction, length);
+                    length = 0;
+                }
+                direction = step;
+                length += 1;
+                point = next;
+                if point == start {
+                    break;
+                }
+            }
+            path.push('z');
+            outline.push((start, path));
+        }
+        outline
+    }
+}
+
+fn write_outline(path: &mut String, outline: &[((isize, isize), String)], x: usize, y: usize) {
+    for ((start_x, start_y), segments) in outline.iter() {
+        write!(
+            path,
+            "M{} {}{}",
+            x as isize + start_x,
+            y as isize + start_y,
+            segments
+        )
+        .unwrap();
+    }
+}
+
+fn write_segment(path: &mut String, direction: (isize, isize), length: isize) {
+    match direction {
+        (dx, 0) => write!(path, "h{}", dx * length).unwrap(),
+        (_, dy) => write!(path, "v{}", dy * length).unwrap(),
+    }
+}
+
+fn hex(rgb: &[u8; 3]) -> String {
+    format!("#{:02x}{:02x}{:02x}", rgb[0], rgb[1], rgb[2])
+}
+
+fn symbol_id(cell: &Cell) -> String {
+    match cell.font {
+        Some(id) => format!("g{}-{}", id, cell.code),
+        None => format!("g{}", cell.code),
+    }
+}
+
+impl Svg {
+    pub fn from_canvas(
+        canvas: &Canvas,
+        font: &Font,
+        glyphs: SvgGlyphs,
+        letter_spacing: &LetterSpacing,
+        aspect_ratio: &AspectRatio,
+    ) -> Svg {
+        let cell_width = match letter_spacing {
+            LetterSpacing::NinePixels if font.width == 8 => 9,
+            _ => font.width,
+        };
+        let cell_height = font.height;
+        let width = canvas.width * cell_width;
+        let height = canvas.height * cell_height;
+        let display_height = match aspect_ratio {
+            AspectRatio::Legacy if cell_width == 9 => height * 27 / 20,
+            AspectRatio::Legacy => height * 6 / 5,
+            AspectRatio::Modern => height,
+        };
+        let fonts = syncterm_fonts(canvas, font);
+        let mut outlines = HashMap::new();
+        for cell in canvas.cells.iter() {
+            outlines.entry((cell.font, cell.code)).or_insert_with(|| {
+                let cell_font = match cell.font.and_then(|id| fonts[&id].as_ref()) {
+                    Some(selected) => selected,
+                    None => font,
+                };
+                Glyph::new(cell_font, cell.code as usize, cell_width).outline()
+            });
+        }
+        let mut body = String::new();
+        for (y, row) in canvas.cells.chunks(canvas.width.max(1)).enumerate() {
+            let mut start = 0;
+            while start < row.len() {
+                let bg = row[start].bg;
+                let mut end = start + 1;
+                while end < row.len() && row[end].bg == bg {
+                    end += 1;
+                }
+                writeln!(
+                    body,
+                    "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>",
+                    start * cell_width,
+                    y * cell_height,
+                    (end - start) * cell_width,
+                    cell_height,
+                    hex(&VGA_PALETTE[bg as usize])
+                )
+                .unwrap();
+                start = end;
+            }
+        }
+        match glyphs {
+            SvgGlyphs::Paths => {
+                let mut paths: BTreeMap<u8, String> = BTreeMap::new();
+                for (i, cell) in canvas.cells.iter().enumerate() {
+                    write_outline(
+                        paths.entry(cell.fg).or_default(),
+                        &outlines[&(cell.font, cell.code)],
+                        (i % canvas.width) * cell_width,
+                        (i / canvas.width) * cell_height,
+                    );
+                }
+                for (fg, path) in paths.iter().filter(|(_, path)| !path.is_empty()) {
+                    writeln!(
+                        body,
+                        "<path fill=\"{}\" d=\"{}\"/>",
+                        hex(&VGA_PALETTE[*fg as usize]),
+                        path
+                    )
+                    .unwrap();
+                }
+            }
+            SvgGlyphs::Symbols => {
+                let mut symbols: BTreeMap<String, String> = BTreeMap::new();
+                for cell in canvas.cells.iter() {
+                    let outline = &outlines[&(cell.font, cell.code)];
+                    if !outline.is_empty() {
+                        symbols.entry(symbol_id(cell)).or_insert_with(|| {
+                            let mut path = String::new();
+                            write_outline(&mut path, outline, 0, 0);
+                            path
+                        });
+                    }
+                }
+                body.push_str("<defs>\n");
+                for (id, outline) in symbols.iter() {
+                    writeln!(
+                        body,
+                        "<symbol id=\"{}\" overflow=\"visible\"><path d=\"{}\"/></symbol>",
+                        id, outline
+                    )
+                    .unwrap();
+                }
+                body.push_str("</defs>\n");
+                for (i, cell) in canvas.cells.iter().enumerate() {
+                    let id = symbol_id(cell);
+                    if !symbols.contains_key(&id) {
+                        continue;
+                    }
+                    writeln!(
+                        body,
+                        "<use href=\"#{}\" x=\"{}\" y=\"{}\" fill=\"{}\"/>",
+                        id,
+                        (i % canvas.width) * cell_width,
+                        (i / canvas.width) * cell_height,
+                        hex(&VGA_PALETTE[cell.fg as usize])
+                    )
+                    .unwrap();
+                }
+            }
+        }
+        Svg {
+            width,
+            height,
+            display_height,
+            body,
+        }
+    }
+
+    pub fn as_document(&self) -> String {
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" \
+             viewBox=\"0 0 {} {}\" preserveAspectRatio=\"none\" shape-rendering=\"crispEdges\">\n\
+             {}</svg>\n",
+            self.width, self.display_height, self.width, self.height, self.body
+        )
+    }
+
+    pub fn as_svg_file<P: AsRef<Path>>(&self, file: P) -> Result<()> {
+        write_bytes_to_file(self.as_document().as_bytes(), file)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn font() -> Font {
+        let mut bitmasks = vec![vec![false; 4]; 256];
+        bitmasks[1] = vec![true; 4];
+        bitmasks[0xc0] = vec![false, true, false, true];
+        bitmasks[0x03] = vec![false, true, false, true];
+        Font {
+            width: 2,
+            height: 2,
+            length: 256,
+            bitmasks,
+        }
+    }
+
+    fn canvas() -> Canvas {
+        let mut canvas = Canvas::new(3, 1);
+        for (x, code) in [1, 1, 0xc0].iter().enumerate() {
+            let cell = Cell {
+                code: *code,
+                fg: 15,
+                bg: if x == 0 { 1 } else { 0 },
+                ..Cell::default()
+            };
+            canvas.set(x, 0, cell);
+        }
+        canvas
+    }
+
+    #[test]
+    fn glyph_outlines() {
+        let font = font();
+        let (start, path) = &Glyph::new(&font, 1, 2).outline()[0];
+        assert_eq!((*start, path.as_str()), ((0, 0), "h2v2h-2z"));
+        let box_drawing = Glyph::new(&font, 0xc0, 3);
+        assert_eq!(box_drawing.pixels, [false, true, true, false, true, true]);
+        let other = Glyph::new(&font, 0x03, 3);
+        assert_eq!(other.pixels, [false, true, false, false, true, false]);
+        assert!(Glyph::new(&font, 0, 2).outline().is_empty());
+    }
+
+    #[test]
+    fn paths_group_by_color() {
+        let svg = Svg::from_canvas(
+            &canvas(),
+            &font(),
+            SvgGlyphs::Paths,
+            &LetterSpacing::EightPixels,
+            &AspectRatio::Modern,
+        );
+        assert_eq!((svg.width, svg.height, svg.display_height), (6, 2, 2));
+        assert_eq!(
+            svg.body,
+            "<rect x=\"0\" y=\"0\" width=\"2\" height=\"2\" fill=\"#0000aa\"/>\n\
+             <rect x=\"2\" y=\"0\" width=\"4\" height=\"2\" fill=\"#000000\"/>\n\
+             <path fill=\"#ffffff\" d=\"M0 0h2v2h-2zM2 0h2v2h-2zM5 0h1v2h-1z\"/>\n"
+        );
+    }
+
+    #[test]
+    fn symbols_are_reused() {
+        let svg = Svg::from_canvas(
+            &canvas(),
+            &font(),
+            SvgGlyphs::Symbols,
+            &LetterSpacing::EightPixels,
+            &AspectRatio::Legacy,
+        );
+        assert_eq!(svg.display_height, 2 * 6 / 5);
+        assert_eq!(svg.body.matches("<symbol id=\"g1\"").count(), 1);
+        assert!(svg
+            .body
+            .contains("<use href=\"#g1\" x=\"2\" y=\"0\" fill=\"#ffffff\"/>"));
+        assert!(svg.body.contains("<use href=\"#g192\" x=\"4\" y=\"0\""));
+        let document = svg.as_document();
+        assert!(document.contains("width=\"6\" height=\"2\" viewBox=\"0 0 6 2\""));
+        assert!(document.ends_with("</svg>\n"));
+    }
+
+    #[test]
+    fn nine_pixel_cells() {
+        let svg = Svg::from_canvas(
+            &Canvas::new(2, 1),
+            &Font::default(),
+            SvgGlyphs::Paths,
+            &LetterSpacing::NinePixels,
+            &AspectRatio::Legacy,
+        );
+        assert_eq!(svg.width, 18);
+        assert_eq!(svg.display_height, svg.height * 27 / 20);
+    }
+}
@@ -0,0 +1,193 @@
+use crate::canvas::Canvas;
+use crate::render::{Image, VGA_PALETTE};
+use crate::string::CP437Char;
+use std::fmt::Write;
+
+static ANSI_ORDER: [u8; 8] = [0, 4, 2, 6, 1, 5, 3, 7];
+static CUBE_LEVELS: [u8; 6] = [0x00, 0x5f, 0x87, 0xaf, 0xd7, 0xff];
+
+#[derive(Clone, Copy)]
+pub enum TerminalColors {
+    TrueColor,
+    Palette256,
+    Palette16,
+}
+
+fn distance(a: &[u8; 3], b: &[u8; 3]) -> usize {
+    a.iter()
+        .zip(b.iter())
+        .map(|(a, b)| (*a as isize - *b as isize).pow(2) as usize)
+        .sum()
+}
+
+fn nearest_256(rgb: &[u8; 3]) -> u8 {
+    let cube = (16..232).map(|i: usize| {
+        let i = i - 16;
+        let rgb = [
+            CUBE_LEVELS[i / 36],
+            CUBE_LEVELS[(i / 6) % 6],
+            CUBE_LEVELS[i % 6],
+        ];
+        (i + 16, rgb)
+    });
+    let grays = (232..256).map(|i: usize| {
+        let level = (8 + (i - 232) * 10) as u8;
+        (i, [level, level, level])
+    });
+    cube.chain(grays)
+        .min_by_key(|(_, candidate)| distance(rgb, candidate))
+        .map(|(i, _)| i as u8)
+        .unwrap()
+}
+
+fn nearest_16(rgb: &[u8; 3]) -> u8 {
+    let vga = VGA_PALETTE
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, candidate)| distance(rgb, candidate))
+        .map(|(i, _)| i)
+        .unwrap();
+    ANSI_ORDER[vga % 8] + (vga as u8 / 8) * 8
+}
+
+fn sgr(rgb: &[u8; 3], background: bool, colors: TerminalColors) -> String {
+    match colors {
+        TerminalColors::TrueColor => {
+            let layer = if background { 48 } else { 38 };
+            format!("{};2;{};{};{}", layer, rgb[0], rgb[1], rgb[2])
+        }
+        TerminalColors::Palette256 => {
+            let layer = if background { 48 } else { 38 };
+            format!("{};5;{}", layer, nearest_256(rgb))
+        }
+        TerminalColors::Palette16 => {
+            let color = nearest_16(rgb);
+            let base = match (background, color >= 8) {
+                (false, false) => 30,
+                (false, true) => 90,
+                (true, false) => 40,
+                (true, true) => 100,
+            };
+            format!("{}", base + color % 8)
+        }
+    }
+}
+
+pub fn canvas_to_terminal(canvas: &Canvas, colors: TerminalColors) -> String {
+    let mut output = String::new();
+    for row in canvas.cells.chunks(canvas.width.max(1)) {
+        let mut current = None;
+        for cell in row {
+            let attributes = (cell.fg, cell.bg, cell.blink);
+            if current != Some(attributes) {
+                write!(
+                    output,
+                    "\x1b[0;{};{}{}m",
+                    sgr(&VGA_PALETTE[cell.fg as usize], false, colors),
+                    sgr(&VGA_PALETTE[cell.bg as usize], true, colors),
+                    if cell.blink { ";5" } else { "" }
+                )
+                .unwrap();
+                current = Some(attributes);
+            }
+            match cell.code {
+                0 | 255 => output.push(' '),
+                code => output.push(char::from_cp437_byte(code)),
+            }
+        }
+        output.push_str("\x1b[0m\n");
+    }
+    output
+}
+
+pub fn image_to_terminal(image: &Image, colors: TerminalColors) -> String {
+    let mut output = String::new();
+    let pixel = |x: usize, y: usize| {
+        let i = (y * image.width + x) * 3;
+        [image.data[i], image.data[i + 1], image.data[i + 2]]
+    };
+    for y in (0..image.height).step_by(2) {
+        let mut current = None;
+        for x in 0..image.width {
+            let top = pixel(x, y);
+            let bottom = if y + 1 < image.height {
+                pixel(x, y + 1)
+            } else {
+                [0, 0, 0]
+            };
+            if current != Some((top, bottom)) {
+                write!(
+                    output,
+                    "\x1b[0;{};{}m",
+                    sgr(&top, false, colors),
+                    sgr(&bottom, true, colors)
+                )
+                .unwrap();
+                current = Some((top, bottom));
+            }
+            output.push('\u{2580}');
+        }
+        output.push_str("\x1b[0m\n");
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::Cell;
+
+    #[test]
+    fn nearest_colors() {
+        assert_eq!(nearest_256(&[0, 0, 0]), 16);
+        assert_eq!(nearest_256(&[0xff, 0xff, 0xff]), 231);
+        assert_eq!(nearest_256(&[0x80, 0x80, 0x80]), 244);
+        assert_eq!(nearest_256(&[0xd7, 0x00, 0x5f]), 161);
+        assert_eq!(nearest_16(&VGA_PALETTE[1]), 4);
+        assert_eq!(nearest_16(&VGA_PALETTE[6]), 3);
+        assert_eq!(nearest_16(&VGA_PALETTE[12]), 9);
+    }
+
+    #[test]
+    fn canvas_attributes() {
+        let mut canvas = Canvas::new(3, 1);
+        let cell = Cell {
+            code: 0xdb,
+            fg: 12,
+            bg: 1,
+            blink: true,
+            font: None,
+        };
+        canvas.set(0, 0, cell);
+        canvas.set(1, 0, cell);
+        canvas.set(
+            2,
+            0,
+            Cell {
+                code: 0,
+                ..Cell::default()
+            },
+        );
+        assert_eq!(
+            canvas_to_terminal(&canvas, TerminalColors::Palette16),
+            "\x1b[0;91;44;5m\u{2588}\u{2588}\x1b[0;37;40m \x1b[0m\n"
+        );
+        assert_eq!(
+            canvas_to_terminal(&canvas, TerminalColors::TrueColor),
+            "\x1b[0;38;2;255;85;85;48;2;0;0;170;5m\u{2588}\u{2588}\
+             \x1b[0;38;2;170;170;170;48;2;0;0;0m \x1b[0m\n"
+        );
+    }
+
+    #[test]
+    fn image_half_blocks() {
+        let mut image = Image::new(2, 3);
+        image.fill_rect(0, 0, 2, 1, &[0xff, 0xff, 0xff]);
+        image.fill_rect(1, 1, 1, 2, &[0xff, 0, 0]);
+        assert_eq!(
+            image_to_terminal(&image, TerminalColors::Palette256),
+            "\x1b[0;38;5;231;48;5;16m\u{2580}\x1b[0;38;5;231;48;5;196m\u{2580}\x1b[0m\n\
+             \x1b[0;38;5;16;48;5;16m\u{2580}\x1b[0;38;5;196;48;5;16m\u{2580}\x1b[0m\n"
+        );
+    }
+}
@@ -1,19 +1,15 @@
-use crate::sauce::error::SauceError;
+use crate::sauce::SauceError;
+use crate::Error;
 use serde::{Deserialize, Serialize};
 pub use std::str::FromStr;
 
-#[derive(Deserialize, Serialize, Clone)]
+#[derive(Deserialize, Serialize, Clone, Default)]
 pub enum AspectRatio {
+    #[default]
     Modern,
     Legacy,
 }
 
-impl Default for AspectRatio {
-    fn default() -> AspectRatio {
-        AspectRatio::Modern
-    }
-}
-
 impl std::fmt::Display for AspectRatio {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
@@ -25,12 +21,12 @@ impl std::fmt::Display for AspectRatio {
 }
 
 impl FromStr for AspectRatio {
-    type Err = Box<SauceError>;
-    fn from_str(string: &str) -> Result<AspectRatio, Box<SauceError>> {
+    type Err = Error;
+    fn from_str(string: &str) -> Result<AspectRatio, Error> {
         match string {
             "modern" => Ok(AspectRatio::Modern),
             "legacy" => Ok(AspectRatio::Legacy),
-            _ => Err(Box::new(SauceError::InvalidAspectRatioValue)),
+            _ => Err(SauceError::InvalidAspectRatioValue.into()),
         }
     }
 }
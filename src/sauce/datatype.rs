@@ -1,8 +1,9 @@
-use crate::sauce::error::SauceError;
+use crate::sauce::SauceError;
+use crate::Error;
 use serde::{Deserialize, Serialize};
 pub use std::str::FromStr;
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
 pub enum DataType {
     Character,
     Bitmap,
@@ -45,8 +46,8 @@ impl DataType {
 }
 
 impl FromStr for DataType {
-    type Err = Box<SauceError>;
-    fn from_str(string: &str) -> Result<DataType, Box<SauceError>> {
+    type Err = Error;
+    fn from_str(string: &str) -> Result<DataType, Error> {
         match string {
             "Character" => Ok(DataType::Character),
             "Bitmap" => Ok(DataType::Bitmap),
@@ -56,17 +57,17 @@ impl FromStr for DataType {
             "XBin" => Ok(DataType::XBin),
             "Archive" => Ok(DataType::Archive),
             "Executable" => Ok(DataType::Executable),
-            _ => Err(Box::new(SauceError::InvalidDataType)),
+            _ => Err(SauceError::InvalidDataType.into()),
         }
     }
 }
 
 pub trait AsDataType {
-    fn as_datatype(&self) -> Result<Option<DataType>, Box<SauceError>>;
+    fn as_datatype(&self) -> Result<Option<DataType>, Error>;
 }
 
 impl AsDataType for u8 {
-    fn as_datatype(&self) -> Result<Option<DataType>, Box<SauceError>> {
+    fn as_datatype(&self) -> Result<Option<DataType>, Error> {
         match self {
             0 => Ok(None),
             1 => Ok(Some(DataType::Character)),
@@ -77,7 +78,7 @@ impl AsDataType for u8 {
             6 => Ok(Some(DataType::XBin)),
             7 => Ok(Some(DataType::Archive)),
             8 => Ok(Some(DataType::Executable)),
-            _ => Err(Box::new(SauceError::InvalidDataType)),
+            _ => Err(SauceError::InvalidDataType.into()),
         }
     }
 }
@@ -1,6 +1,7 @@
-pub use std::error::Error;
+use std::error::Error;
 
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum SauceError {
     EOFValueNotFound,
     InvalidDataType,
@@ -9,18 +10,24 @@ pub enum SauceError {
     InvalidLetterSpacingValue,
     InvalidAspectRatioValue,
     CommentsTooLarge,
+    FieldTooLarge,
+    InvalidInfoField,
+    InfoValueTooLarge,
 }
 
 impl std::fmt::Display for SauceError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
-            SauceError::EOFValueNotFound => writeln!(f, "EOF value not found"),
-            SauceError::InvalidDataType => writeln!(f, "Invalid datatype"),
-            SauceError::InvalidFileType => writeln!(f, "Invalid filetype"),
-            SauceError::CommentsNotFound => writeln!(f, "Comments not found"),
-            SauceError::InvalidLetterSpacingValue => writeln!(f, "Invalid letter spacing value"),
-            SauceError::InvalidAspectRatioValue => writeln!(f, "Invalid aspect ratio value"),
-            SauceError::CommentsTooLarge => writeln!(f, "Comments too large"),
+            SauceError::EOFValueNotFound => write!(f, "EOF value not found"),
+            SauceError::InvalidDataType => write!(f, "Invalid datatype"),
+            SauceError::InvalidFileType => write!(f, "Invalid filetype"),
+            SauceError::CommentsNotFound => write!(f, "Comments not found"),
+            SauceError::InvalidLetterSpacingValue => write!(f, "Invalid letter spacing value"),
+            SauceError::InvalidAspectRatioValue => write!(f, "Invalid aspect ratio value"),
+            SauceError::CommentsTooLarge => write!(f, "Comments too large"),
+            SauceError::FieldTooLarge => write!(f, "Field too large"),
+            SauceError::InvalidInfoField => write!(f, "Invalid type info field for filetype"),
+            SauceError::InfoValueTooLarge => write!(f, "Type info value too large"),
         }
     }
 }
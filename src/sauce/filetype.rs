@@ -1,9 +1,10 @@
 use crate::sauce::{DataType, SauceError};
+use crate::Error;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 pub use std::str::FromStr;
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
 pub enum FileType {
     ASCII,
     ANSI,
@@ -214,8 +215,8 @@ impl FileType {
 }
 
 impl FromStr for FileType {
-    type Err = Box<SauceError>;
-    fn from_str(string: &str) -> Result<FileType, Box<SauceError>> {
+    type Err = Error;
+    fn from_str(string: &str) -> Result<FileType, Error> {
         match string {
             "ASCII" => Ok(FileType::ASCII),
             "ANSI" => Ok(FileType::ANSI),
@@ -290,22 +291,18 @@ impl FromStr for FileType {
                         }
                     }
                 }
-                Err(Box::new(SauceError::InvalidFileType))
+                Err(SauceError::InvalidFileType.into())
             }
         }
     }
 }
 
 pub trait AsFileType {
-    fn as_filetype(&self, datatype: &Option<DataType>)
-        -> Result<Option<FileType>, Box<SauceError>>;
+    fn as_filetype(&self, datatype: &Option<DataType>) -> Result<Option<FileType>, Error>;
 }
 
 impl AsFileType for u8 {
-    fn as_filetype(
-        &self,
-        datatype: &Option<DataType>,
-    ) -> Result<Option<FileType>, Box<SauceError>> {
+    fn as_filetype(&self, datatype: &Option<DataType>) -> Result<Option<FileType>, Error> {
         match datatype {
             Some(DataType::Character) => match self {
                 0 => Ok(Some(FileType::ASCII)),
@@ -317,7 +314,7 @@ impl AsFileType for u8 {
                 6 => Ok(Some(FileType::HTML)),
                 7 => Ok(Some(FileType::Source)),
                 8 => Ok(Some(FileType::TundraDraw)),
-                _ => Err(Box::new(SauceError::InvalidFileType)),
+                _ => Err(SauceError::InvalidFileType.into()),
             },
             Some(DataType::Bitmap) => match self {
                 0 => Ok(Some(FileType::GIF)),
@@ -334,14 +331,14 @@ impl AsFileType for u8 {
                 11 => Ok(Some(FileType::JPG)),
                 12 => Ok(Some(FileType::MPG)),
                 13 => Ok(Some(FileType::AVI)),
-                _ => Err(Box::new(SauceError::InvalidFileType)),
+                _ => Err(SauceError::InvalidFileType.into()),
             },
             Some(DataType::Vector) => match self {
                 0 => Ok(Some(FileType::DXF)),
                 1 => Ok(Some(FileType::DWG)),
                 2 => Ok(Some(FileType::WPGVector)),
                 3 => Ok(Some(FileType::Studio3DS)),
-                _ => Err(Box::new(SauceError::InvalidFileType)),
+                _ => Err(SauceError::InvalidFileType.into()),
             },
             Some(DataType::Audio) => match self {
                 0 => Ok(Some(FileType::MOD)),
@@ -369,12 +366,12 @@ impl AsFileType for u8 {
                 22 => Ok(Some(FileType::XM)),
                 23 => Ok(Some(FileType::HSC)),
                 24 => Ok(Some(FileType::IT)),
-                _ => Err(Box::new(SauceError::InvalidFileType)),
+                _ => Err(SauceError::InvalidFileType.into()),
             },
             Some(DataType::BinaryText) => Ok(Some(FileType::Variable(*self))),
             Some(DataType::XBin) => {
                 if *self != 0 {
-                    Err(Box::new(SauceError::InvalidFileType))
+                    Err(SauceError::InvalidFileType.into())
                 } else {
                     Ok(Some(FileType::XBin))
                 }
@@ -390,11 +387,11 @@ impl AsFileType for u8 {
                 7 => Ok(Some(FileType::UC2)),
                 8 => Ok(Some(FileType::PAK)),
                 9 => Ok(Some(FileType::SQZ)),
-                _ => Err(Box::new(SauceError::InvalidFileType)),
+                _ => Err(SauceError::InvalidFileType.into()),
             },
             Some(DataType::Executable) | None => {
                 if *self != 0 {
-                    Err(Box::new(SauceError::InvalidFileType))
+                    Err(SauceError::InvalidFileType.into())
                 } else {
                     Ok(None)
                 }
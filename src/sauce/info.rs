@@ -0,0 +1,306 @@
+use crate::sauce::{DataType, FileType, Sauce, SauceError};
+use crate::Error;
+use serde_json::{Map, Value};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InfoField {
+    CharacterWidth,
+    NumberOfLines,
+    CharacterScreenHeight,
+    PixelWidth,
+    PixelHeight,
+    PixelDepth,
+    NumberOfColors,
+    SampleRate,
+}
+
+impl std::fmt::Display for InfoField {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            InfoField::CharacterWidth => write!(f, "character width"),
+            InfoField::NumberOfLines => write!(f, "number of lines"),
+            InfoField::CharacterScreenHeight => write!(f, "character screen height"),
+            InfoField::PixelWidth => write!(f, "pixel width"),
+            InfoField::PixelHeight => write!(f, "pixel height"),
+            InfoField::PixelDepth => write!(f, "pixel depth"),
+            InfoField::NumberOfColors => write!(f, "number of colors"),
+            InfoField::SampleRate => write!(f, "sample rate"),
+        }
+    }
+}
+
+impl InfoField {
+    pub fn key(&self) -> &'static str {
+        match self {
+            InfoField::CharacterWidth => "character_width",
+            InfoField::NumberOfLines => "number_of_lines",
+            InfoField::CharacterScreenHeight => "character_screen_height",
+            InfoField::PixelWidth => "pixel_width",
+            InfoField::PixelHeight => "pixel_height",
+            InfoField::PixelDepth => "pixel_depth",
+            InfoField::NumberOfColors => "number_of_colors",
+            InfoField::SampleRate => "sample_rate",
+        }
+    }
+}
+
+impl FileType {
+    pub fn info_fields(&self) -> [Option<InfoField>; 4] {
+        use self::InfoField::*;
+        match self {
+            FileType::ASCII
+            | FileType::ANSI
+            | FileType::PCBoard
+            | FileType::Avatar
+            | FileType::TundraDraw
+            | FileType::XBin => [Some(CharacterWidth), Some(NumberOfLines), None, None],
+            FileType::ANSImation => [
+                Some(CharacterWidth),
+                Some(CharacterScreenHeight),
+                None,
+                None,
+            ],
+            FileType::RIPScript => [
+                Some(PixelWidth),
+                Some(PixelHeight),
+                Some(NumberOfColors),
+                None,
+            ],
+            FileType::GIF
+            | FileType::PCX
+            | FileType::LBMOrIFF
+            | FileType::TGA
+            | FileType::FLI
+            | FileType::FLC
+            | FileType::BMP
+            | FileType::GL
+            | FileType::DL
+            | FileType::WPGBitmap
+            | FileType::PNG
+            | FileType::JPG
+            | FileType::MPG
+            | FileType::AVI => [Some(PixelWidth), Some(PixelHeight), Some(PixelDepth), None],
+            FileType::SMP8 | FileType::SMP8S | FileType::SMP16 | FileType::SMP16S => {
+                [Some(SampleRate), None, None, None]
+            }
+            _ => [None; 4],
+        }
+    }
+}
+
+impl Sauce {
+    pub fn info_fields(&self) -> [Option<InfoField>; 4] {
+        match &self.filetype {
+            Some(filetype) => filetype.info_fields(),
+            None => [None; 4],
+        }
+    }
+
+    pub(crate) fn info_values(&self) -> [usize; 4] {
+        [self.info_1, self.info_2, self.info_3, self.info_4]
+    }
+
+    pub fn info(&self, field: InfoField) -> Option<usize> {
+        let index = self
+            .info_fields()
+            .iter()
+            .position(|info_field| *info_field == Some(field))?;
+        Some(self.info_values()[index])
+    }
+
+    pub fn set_info(&mut self, field: InfoField, value: usize) -> Result<(), Error> {
+        let index = self
+            .info_fields()
+            .iter()
+            .position(|info_field| *info_field == Some(field))
+            .ok_or_else(|| Error::from(SauceError::InvalidInfoField).with_field(field.key()))?;
+        if value > u16::MAX as usize {
+            return Err(Error::from(SauceError::InfoValueTooLarge).with_field(field.key()));
+        }
+        match index {
+            0 => self.info_1 = value,
+            1 => self.info_2 = value,
+            2 => self.info_3 = value,
+            _ => self.info_4 = value,
+        }
+        Ok(())
+    }
+
+    pub fn described_info(&self) -> Vec<(InfoField, usize)> {
+        self.info_fields()
+            .iter()
+            .zip(self.info_values().iter())
+            .filter_map(|(field, value)| field.map(|field| (field, *value)))
+            .collect()
+    }
+
+    pub fn character_dimensions(&self) -> Option<(usize, usize)> {
+        if let (Some(DataType::BinaryText), Some(FileType::Variable(half_width))) =
+            (&self.datatype, &self.filetype)
+        {
+            let width = *half_width as usize * 2;
+            if width == 0 {
+                return None;
+            }
+            return Some((width, self.filesize / (width * 2)));
+        }
+        let width = self.info(InfoField::CharacterWidth)?;
+        let height = self
+            .info(InfoField::NumberOfLines)
+            .or_else(|| self.info(InfoField::CharacterScreenHeight))?;
+        Some((width, height))
+    }
+
+    pub fn set_character_dimensions(&mut self, width: usize, height: usize) -> Result<(), Error> {
+        if let Some(DataType::BinaryText) = self.datatype {
+            if width == 0 || width % 2 != 0 || width / 2 > u8::MAX as usize {
+                return Err(Error::from(SauceError::InvalidInfoField).with_field("filetype"));
+            }
+            self.filetype = Some(FileType::Variable((width / 2) as u8));
+            return Ok(());
+        }
+        self.set_info(InfoField::CharacterWidth, width)?;
+        match self.filetype {
+            Some(FileType::ANSImation) => self.set_info(InfoField::CharacterScreenHeight, height),
+            _ => self.set_info(InfoField::NumberOfLines, height),
+        }
+    }
+
+    pub fn pixel_dimensions(&self) -> Option<(usize, usize)> {
+        Some((
+            self.info(InfoField::PixelWidth)?,
+            self.info(InfoField::PixelHeight)?,
+        ))
+    }
+
+    pub fn set_pixel_dimensions(&mut self, width: usize, height: usize) -> Result<(), Error> {
+        self.set_info(InfoField::PixelWidth, width)?;
+        self.set_info(InfoField::PixelHeight, height)
+    }
+
+    pub fn pixel_depth(&self) -> Option<usize> {
+        self.info(InfoField::PixelDepth)
+    }
+
+    pub fn set_pixel_depth(&mut self, depth: usize) -> Result<(), Error> {
+        self.set_info(InfoField::PixelDepth, depth)
+    }
+
+    pub fn number_of_colors(&self) -> Option<usize> {
+        self.info(InfoField::NumberOfColors)
+    }
+
+    pub fn set_number_of_colors(&mut self, colors: usize) -> Result<(), Error> {
+        self.set_info(InfoField::NumberOfColors, colors)
+    }
+
+    pub fn sample_rate(&self) -> Option<usize> {
+        self.info(InfoField::SampleRate)
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: usize) -> Result<(), Error> {
+        self.set_info(InfoField::SampleRate, sample_rate)
+    }
+
+    pub fn to_json_value(&self) -> serde_json::Result<Value> {
+        let mut value = serde_json::to_value(self)?;
+        let mut info = Map::new();
+        for (field, field_value) in self.described_info() {
+            info.insert(field.key().to_string(), Value::from(field_value));
+        }
+        if let Value::Object(map) = &mut value {
+            map.insert("type_info".to_string(), Value::Object(info));
+        }
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sauce(datatype: DataType, filetype: FileType) -> Sauce {
+        let mut sauce = Sauce::new();
+        sauce.datatype = Some(datatype);
+        sauce.filetype = Some(filetype);
+        sauce
+    }
+
+    #[test]
+    fn character_dimensions() {
+        let mut ansi = sauce(DataType::Character, FileType::ANSI);
+        ansi.set_character_dimensions(80, 25).unwrap();
+        assert_eq!((ansi.info_1, ansi.info_2), (80, 25));
+        assert_eq!(ansi.character_dimensions(), Some((80, 25)));
+        let mut ansimation = sauce(DataType::Character, FileType::ANSImation);
+        ansimation.set_character_dimensions(80, 50).unwrap();
+        assert_eq!(ansimation.info(InfoField::CharacterScreenHeight), Some(50));
+        assert_eq!(ansimation.info(InfoField::NumberOfLines), None);
+        assert_eq!(ansimation.character_dimensions(), Some((80, 50)));
+    }
+
+    #[test]
+    fn binary_text_width_is_in_filetype() {
+        let mut bin = sauce(DataType::BinaryText, FileType::Variable(0));
+        assert_eq!(bin.character_dimensions(), None);
+        bin.set_character_dimensions(160, 0).unwrap();
+        assert!(matches!(bin.filetype, Some(FileType::Variable(80))));
+        bin.filesize = 160 * 2 * 10;
+        assert_eq!(bin.character_dimensions(), Some((160, 10)));
+        assert!(bin.set_character_dimensions(81, 0).is_err());
+        assert!(bin.set_character_dimensions(512, 0).is_err());
+    }
+
+    #[test]
+    fn bitmap_and_audio_fields() {
+        let mut png = sauce(DataType::Bitmap, FileType::PNG);
+        png.set_pixel_dimensions(640, 480).unwrap();
+        png.set_pixel_depth(24).unwrap();
+        assert_eq!(png.pixel_dimensions(), Some((640, 480)));
+        assert_eq!(png.pixel_depth(), Some(24));
+        assert_eq!(png.number_of_colors(), None);
+        let mut smp = sauce(DataType::Audio, FileType::SMP16);
+        smp.set_sample_rate(44100).unwrap();
+        assert_eq!(smp.sample_rate(), Some(44100));
+        assert_eq!(smp.described_info(), vec![(InfoField::SampleRate, 44100)]);
+        let wav = sauce(DataType::Audio, FileType::WAV);
+        assert_eq!(wav.info_fields(), [None; 4]);
+        assert_eq!(wav.sample_rate(), None);
+    }
+
+    #[test]
+    fn invalid_fields_are_rejected() {
+        let mut ansi = sauce(DataType::Character, FileType::ANSI);
+        let error = ansi.set_sample_rate(8000).unwrap_err();
+        assert!(matches!(
+            error,
+            Error::Sauce {
+                kind: SauceError::InvalidInfoField,
+                field: Some("sample_rate"),
+                ..
+            }
+        ));
+        let error = ansi.set_info(InfoField::CharacterWidth, 70000).unwrap_err();
+        assert!(matches!(
+            error,
+            Error::Sauce {
+                kind: SauceError::InfoValueTooLarge,
+                field: Some("character_width"),
+                ..
+            }
+        ));
+        assert_eq!(Sauce::new().info(InfoField::CharacterWidth), None);
+    }
+
+    #[test]
+    fn json_type_info() {
+        let mut rip = sauce(DataType::Character, FileType::RIPScript);
+        rip.set_pixel_dimensions(640, 350).unwrap();
+        rip.set_number_of_colors(16).unwrap();
+        let value = rip.to_json_value().unwrap();
+        assert_eq!(
+            value["type_info"],
+            serde_json::json!({"pixel_width": 640, "pixel_height": 350, "number_of_colors": 16})
+        );
+    }
+}
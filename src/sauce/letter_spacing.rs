@@ -1,19 +1,15 @@
-use crate::sauce::error::SauceError;
+use crate::sauce::SauceError;
+use crate::Error;
 use serde::{Deserialize, Serialize};
 pub use std::str::FromStr;
 
-#[derive(Deserialize, Serialize, Clone)]
+#[derive(Deserialize, Serialize, Clone, Default)]
 pub enum LetterSpacing {
+    #[default]
     EightPixels,
     NinePixels,
 }
 
-impl Default for LetterSpacing {
-    fn default() -> LetterSpacing {
-        LetterSpacing::EightPixels
-    }
-}
-
 impl std::fmt::Display for LetterSpacing {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
@@ -25,12 +21,12 @@ impl std::fmt::Display for LetterSpacing {
 }
 
 impl FromStr for LetterSpacing {
-    type Err = Box<SauceError>;
-    fn from_str(string: &str) -> Result<LetterSpacing, Box<SauceError>> {
+    type Err = Error;
+    fn from_str(string: &str) -> Result<LetterSpacing, Error> {
         match string {
             "8px" => Ok(LetterSpacing::EightPixels),
             "9px" => Ok(LetterSpacing::NinePixels),
-            _ => Err(Box::new(SauceError::InvalidLetterSpacingValue)),
+            _ => Err(SauceError::InvalidLetterSpacingValue.into()),
         }
     }
 }
@@ -2,17 +2,68 @@ mod aspect_ratio;
 mod datatype;
 mod error;
 mod filetype;
+mod info;
 mod letter_spacing;
+mod validate;
+mod warning;
 pub use self::aspect_ratio::AspectRatio;
 pub use self::datatype::{AsDataType, DataType};
-use self::error::SauceError;
+pub use self::error::SauceError;
 pub use self::filetype::{AsFileType, FileType};
+pub use self::info::InfoField;
 pub use self::letter_spacing::LetterSpacing;
+pub(crate) use self::validate::flags_supported;
+pub use self::validate::Problem;
+pub use self::warning::Warning;
 use crate::ascii;
 use crate::bytes::*;
+use crate::error::{Context, Result};
 use crate::string::*;
-use error::Error;
+use crate::Error;
 use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+static MAX_RECORD_LENGTH: u64 = 1 + 5 + 255 * 64 + 128;
+
+fn read_tail<R: Read + Seek>(reader: &mut R, end: u64) -> Result<(usize, Vec<u8>)> {
+    let start = end.saturating_sub(MAX_RECORD_LENGTH);
+    reader.seek(SeekFrom::Start(start))?;
+    let mut tail = vec![0; (end - start) as usize];
+    reader
+        .read_exact(&mut tail)
+        .map_err(|error| Error::from(error).with_offset(start as usize))?;
+    Ok((start as usize, tail))
+}
+
+fn open_for_update<P: AsRef<Path>>(path: P) -> Result<File> {
+    OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(&path)
+        .with_path(&path)
+}
+
+fn field_too_large(field: &'static str) -> impl Fn(BytesError) -> Error {
+    move |_| Error::from(SauceError::FieldTooLarge).with_field(field)
+}
+
+fn warning_error(warning: &Warning, record_start: usize, content_end: usize) -> Option<Error> {
+    let (field, offset) = match warning {
+        Warning::InvalidDataType { .. } => ("datatype", record_start + 94),
+        Warning::InvalidFileType { .. } => ("filetype", record_start + 95),
+        Warning::InvalidLetterSpacing | Warning::InvalidAspectRatio => {
+            ("flags", record_start + 105)
+        }
+        Warning::CommentsNotFound { .. } | Warning::CommentCountMismatch { .. } => {
+            ("comments", record_start + 104)
+        }
+        Warning::MissingEOF | Warning::DuplicateRecord => ("eof", content_end),
+    };
+    let error = Error::from(warning.as_error()?);
+    Some(error.with_field(field).with_offset(offset))
+}
 
 #[derive(Default, Deserialize, Serialize)]
 pub struct Sauce {
@@ -43,17 +94,32 @@ impl Sauce {
         Default::default()
     }
 
-    pub fn from_file(file: &str) -> Result<Option<Sauce>, Box<dyn Error>> {
-        let bytes = read_file_to_bytes(file)?;
-        if let Some(mut sauce) = Sauce::from_bytes(&bytes)? {
-            sauce.file = Some(file.to_string());
+    pub fn from_file<P: AsRef<Path>>(file: P) -> Result<Option<Sauce>> {
+        let mut reader = File::open(&file).with_path(&file)?;
+        if let Some(mut sauce) = Sauce::from_reader(&mut reader).with_path(&file)? {
+            sauce.file = Some(file.as_ref().display().to_string());
             Ok(Some(sauce))
         } else {
             Ok(None)
         }
     }
 
-    pub fn remove_from_bytes(bytes: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    pub fn actual_filesize(&self) -> usize {
+        self.actual_filesize
+    }
+
+    pub fn from_reader<R: Read + Seek>(reader: &mut R) -> Result<Option<Sauce>> {
+        let end = reader.seek(SeekFrom::End(0))?;
+        let (offset, tail) = read_tail(reader, end)?;
+        if let Some(mut sauce) = Sauce::from_bytes_at(&tail, offset)? {
+            sauce.actual_filesize += offset;
+            Ok(Some(sauce))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn remove_from_bytes(bytes: &[u8]) -> Result<Vec<u8>> {
         if let Some(sauce) = Sauce::from_bytes(bytes)? {
             Ok(bytes[0..sauce.actual_filesize].to_vec())
         } else {
@@ -61,14 +127,16 @@ impl Sauce {
         }
     }
 
-    pub fn remove_from_file(file: &str) -> Result<(), Box<dyn Error>> {
-        let bytes = read_file_to_bytes(file)?;
-        let bytes = Sauce::remove_from_bytes(&bytes)?;
-        write_bytes_to_file(&bytes, file)?;
+    pub fn remove_from_file<P: AsRef<Path>>(path: P) -> Result<()> {
+        let mut file = open_for_update(&path)?;
+        if let Some(sauce) = Sauce::from_reader(&mut file).with_path(&path)? {
+            file.set_len(sauce.actual_filesize as u64)
+                .with_path(&path)?;
+        }
         Ok(())
     }
 
-    pub fn add_to_bytes(&mut self, bytes: &mut Vec<u8>) -> Result<(), Box<dyn Error>> {
+    pub fn add_to_bytes(&mut self, bytes: &mut Vec<u8>) -> Result<()> {
         if let Some(sauce) = Sauce::from_bytes(bytes)? {
             bytes.resize(sauce.actual_filesize, 0);
             self.filesize = sauce.actual_filesize;
@@ -80,33 +148,54 @@ impl Sauce {
         Ok(())
     }
 
-    pub fn add_to_file(&mut self, file: &str) -> Result<(), Box<dyn Error>> {
-        let mut bytes = read_file_to_bytes(file)?;
-        self.add_to_bytes(&mut bytes)?;
-        write_bytes_to_file(&bytes, file)?;
+    pub fn add_to_file<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        let mut file = open_for_update(&path)?;
+        let content_end = match Sauce::from_reader(&mut file).with_path(&path)? {
+            Some(sauce) => sauce.actual_filesize,
+            None => file.seek(SeekFrom::End(0)).with_path(&path)? as usize,
+        };
+        self.write_to_file(&mut file, content_end).with_path(&path)
+    }
+
+    fn write_to_file(&mut self, file: &mut File, content_end: usize) -> Result<()> {
+        self.filesize = content_end;
+        let sauce_bytes = self.to_bytes()?;
+        file.set_len(content_end as u64)?;
+        file.seek(SeekFrom::Start(content_end as u64))?;
+        file.write_all(&sauce_bytes)?;
+        file.flush()?;
+        Ok(())
+    }
+
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_all(&self.to_bytes()?)?;
+        writer.flush()?;
         Ok(())
     }
 
     pub fn to_json(&self) -> serde_json::Result<String> {
-        let json = serde_json::to_string_pretty(&self)?;
+        let json = serde_json::to_string_pretty(&self.to_json_value()?)?;
         Ok(json)
     }
 
-    pub fn to_bytes(&self) -> Result<Vec<u8>, Box<dyn Error>> {
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
         let mut bytes = vec![ascii::EOF];
         let mut comments_length = 0;
         if let Some(comments) = &self.comments {
             let mut comments_bytes = comments.as_cp437_bytes();
             comments_length = (comments_bytes.len() as f32 / 64.0).ceil() as usize;
             if comments_length > 255 {
-                return Err(Box::new(SauceError::CommentsTooLarge));
+                return Err(Error::from(SauceError::CommentsTooLarge).with_field("comments"));
             }
             comments_bytes.pad_with_spaces(comments_length * 64);
             bytes.resize(134 + comments_bytes.len(), ascii::NULL);
             String::from("COMNT")
                 .as_cp437_bytes()
-                .write_to_slice(&mut bytes[1..=5])?;
-            comments_bytes.write_to_slice(&mut bytes[6..6 + comments_bytes.len()])?;
+                .write_to_slice(&mut bytes[1..=5])
+                .map_err(field_too_large("comments"))?;
+            comments_bytes
+                .write_to_slice(&mut bytes[6..6 + comments_bytes.len()])
+                .map_err(field_too_large("comments"))?;
         } else {
             bytes.resize(129, ascii::NULL);
         }
@@ -114,28 +203,35 @@ impl Sauce {
         let sauce_bytes = &mut bytes[sauce_start..];
         String::from("SAUCE00")
             .as_cp437_bytes()
-            .write_to_slice(&mut sauce_bytes[0..=6])?;
+            .write_to_slice(&mut sauce_bytes[0..=6])
+            .map_err(field_too_large("id"))?;
         self.title
             .as_cp437_bytes()
             .pad_with_spaces(35)
-            .write_to_slice(&mut sauce_bytes[7..=41])?;
+            .write_to_slice(&mut sauce_bytes[7..=41])
+            .map_err(field_too_large("title"))?;
         self.author
             .as_cp437_bytes()
             .pad_with_spaces(20)
-            .write_to_slice(&mut sauce_bytes[42..=61])?;
+            .write_to_slice(&mut sauce_bytes[42..=61])
+            .map_err(field_too_large("author"))?;
         self.group
             .as_cp437_bytes()
             .pad_with_spaces(20)
-            .write_to_slice(&mut sauce_bytes[62..=81])?;
+            .write_to_slice(&mut sauce_bytes[62..=81])
+            .map_err(field_too_large("group"))?;
         self.year
             .as_cp437_bytes()
-            .write_to_slice(&mut sauce_bytes[82..=85])?;
+            .write_to_slice(&mut sauce_bytes[82..=85])
+            .map_err(field_too_large("year"))?;
         self.month
             .as_cp437_bytes()
-            .write_to_slice(&mut sauce_bytes[86..=87])?;
+            .write_to_slice(&mut sauce_bytes[86..=87])
+            .map_err(field_too_large("month"))?;
         self.date
             .as_cp437_bytes()
-            .write_to_slice(&mut sauce_bytes[88..=89])?;
+            .write_to_slice(&mut sauce_bytes[88..=89])
+            .map_err(field_too_large("date"))?;
         self.filesize.pack_to_bytes(&mut sauce_bytes[90..=93]);
         sauce_bytes[94] = match &self.datatype {
             Some(datatype) => datatype.as_u8(),
@@ -167,20 +263,43 @@ impl Sauce {
         self.font_name
             .as_cp437_bytes()
             .pad_with_nulls(22)
-            .write_to_slice(&mut sauce_bytes[106..=127])?;
+            .write_to_slice(&mut sauce_bytes[106..=127])
+            .map_err(field_too_large("font_name"))?;
         Ok(bytes.to_vec())
     }
 
-    pub fn from_bytes(bytes: &[u8]) -> Result<Option<Sauce>, Box<dyn Error>> {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Option<Sauce>> {
+        Sauce::from_bytes_at(bytes, 0)
+    }
+
+    fn from_bytes_at(bytes: &[u8], offset: usize) -> Result<Option<Sauce>> {
+        match Sauce::from_bytes_tolerant(bytes) {
+            Some((sauce, warnings)) => {
+                let record_start = offset + bytes.len() - 128;
+                let content_end = offset + sauce.actual_filesize;
+                match warnings
+                    .iter()
+                    .find_map(|warning| warning_error(warning, record_start, content_end))
+                {
+                    Some(error) => Err(error),
+                    None => Ok(Some(sauce)),
+                }
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn from_bytes_tolerant(bytes: &[u8]) -> Option<(Sauce, Vec<Warning>)> {
         let mut sauce = Sauce::new();
-        if bytes.len() < 129 {
-            return Ok(None);
+        let mut warnings = Vec::new();
+        if bytes.len() < 128 {
+            return None;
         }
         let sauce_start = bytes.len() - 128;
         let sauce_bytes = &bytes[sauce_start..];
-        let id = String::from_cp437_bytes(&sauce_bytes[0..=6].to_vec());
+        let id = String::from_cp437_bytes(&sauce_bytes[0..=6]);
         if id != "SAUCE00" {
-            return Ok(None);
+            return None;
         }
         sauce.title =
             String::from_cp437_bytes(sauce_bytes[7..=41].to_vec().strip_trailing_spaces());
@@ -188,12 +307,24 @@ impl Sauce {
             String::from_cp437_bytes(sauce_bytes[42..=61].to_vec().strip_trailing_spaces());
         sauce.group =
             String::from_cp437_bytes(sauce_bytes[62..=81].to_vec().strip_trailing_spaces());
-        sauce.year = String::from_cp437_bytes(&sauce_bytes[82..=85].to_vec());
-        sauce.month = String::from_cp437_bytes(&sauce_bytes[86..=87].to_vec());
-        sauce.date = String::from_cp437_bytes(&sauce_bytes[88..=89].to_vec());
+        sauce.year = String::from_cp437_bytes(&sauce_bytes[82..=85]);
+        sauce.month = String::from_cp437_bytes(&sauce_bytes[86..=87]);
+        sauce.date = String::from_cp437_bytes(&sauce_bytes[88..=89]);
         sauce.filesize = sauce_bytes[90..=93].as_usize();
-        sauce.datatype = sauce_bytes[94].as_datatype()?;
-        sauce.filetype = sauce_bytes[95].as_filetype(&sauce.datatype)?;
+        sauce.datatype = sauce_bytes[94].as_datatype().unwrap_or_else(|_| {
+            warnings.push(Warning::InvalidDataType {
+                value: sauce_bytes[94],
+            });
+            None
+        });
+        sauce.filetype = sauce_bytes[95]
+            .as_filetype(&sauce.datatype)
+            .unwrap_or_else(|_| {
+                warnings.push(Warning::InvalidFileType {
+                    value: sauce_bytes[95],
+                });
+                None
+            });
         sauce.info_1 = sauce_bytes[96..=97].as_usize();
         sauce.info_2 = sauce_bytes[98..=99].as_usize();
         sauce.info_3 = sauce_bytes[100..=101].as_usize();
@@ -205,47 +336,106 @@ impl Sauce {
             0 => None,
             1 => Some(LetterSpacing::EightPixels),
             2 => Some(LetterSpacing::NinePixels),
-            _ => return Err(Box::new(SauceError::InvalidLetterSpacingValue)),
+            _ => {
+                warnings.push(Warning::InvalidLetterSpacing);
+                None
+            }
         };
         sauce.aspect_ratio = match (flags >> 3) & 3 {
             0 => None,
             1 => Some(AspectRatio::Legacy),
             2 => Some(AspectRatio::Modern),
-            _ => return Err(Box::new(SauceError::InvalidAspectRatioValue)),
+            _ => {
+                warnings.push(Warning::InvalidAspectRatio);
+                None
+            }
         };
         sauce.font_name =
             String::from_cp437_bytes(sauce_bytes[106..=127].to_vec().strip_trailing_nulls());
+        let mut content_end = sauce_start;
         if lines_of_comments > 0 {
-            if bytes.len() < 134 + lines_of_comments * 64 {
-                return Err(Box::new(SauceError::CommentsNotFound));
-            }
-            sauce.actual_filesize = bytes.len() - lines_of_comments * 64 - 134;
-            if bytes[sauce.actual_filesize] != ascii::EOF {
-                return Err(Box::new(SauceError::EOFValueNotFound));
-            }
-            let comments_end = bytes.len() - 128;
-            let comments_bytes = &bytes[sauce.actual_filesize + 1..comments_end];
-            let comment_id = String::from_cp437_bytes(&comments_bytes[0..=4].to_vec());
-            if comment_id != "COMNT" {
-                return Err(Box::new(SauceError::CommentsNotFound));
-            }
-            let mut comments = String::new();
-            for i in 0..lines_of_comments {
-                let comment_start = 5 + i * 64;
-                let mut vec = comments_bytes[comment_start..comment_start + 64].to_vec();
-                if i == lines_of_comments - 1 {
-                    vec.strip_trailing_spaces();
+            let has_comments = |lines: &usize| {
+                let start = sauce_start.checked_sub(5 + lines * 64);
+                start.map_or(false, |start| &bytes[start..start + 5] == b"COMNT")
+            };
+            let found = Some(lines_of_comments)
+                .filter(has_comments)
+                .or_else(|| (1..=255).find(has_comments));
+            match found {
+                Some(lines) => {
+                    if lines != lines_of_comments {
+                        warnings.push(Warning::CommentCountMismatch {
+                            recorded: lines_of_comments,
+                            found: lines,
+                        });
+                    }
+                    content_end = sauce_start - 5 - lines * 64;
+                    let mut comments = String::new();
+                    for i in 0..lines {
+                        let comment_start = content_end + 5 + i * 64;
+                        let mut vec = bytes[comment_start..comment_start + 64].to_vec();
+                        if i == lines - 1 {
+                            vec.strip_trailing_spaces();
+                        }
+                        comments.push_str(&String::from_cp437_bytes(&vec));
+                    }
+                    if lines > 0 {
+                        sauce.comments = Some(comments);
+                    }
                 }
-                comments.push_str(&String::from_cp437_bytes(&vec));
+                None => warnings.push(Warning::CommentsNotFound {
+                    lines: lines_of_comments,
+                }),
             }
-            sauce.comments = Some(comments);
+        }
+        if content_end > 0 && bytes[content_end - 1] == ascii::EOF {
+            sauce.actual_filesize = content_end - 1;
         } else {
-            sauce.actual_filesize = bytes.len() - 129;
-            if bytes[sauce.actual_filesize] != ascii::EOF {
-                return Err(Box::new(SauceError::EOFValueNotFound));
+            warnings.push(Warning::MissingEOF);
+            sauce.actual_filesize = content_end;
+        }
+        if Sauce::from_bytes_tolerant(&bytes[..sauce.actual_filesize]).is_some() {
+            warnings.push(Warning::DuplicateRecord);
+        }
+        Some((sauce, warnings))
+    }
+
+    pub fn repair_bytes(bytes: &mut Vec<u8>) -> Result<Option<Vec<Warning>>> {
+        let (mut sauce, warnings) = match Sauce::from_bytes_tolerant(bytes) {
+            Some(result) => result,
+            None => return Ok(None),
+        };
+        let mut content_end = sauce.actual_filesize;
+        while let Some((duplicate, _)) = Sauce::from_bytes_tolerant(&bytes[..content_end]) {
+            content_end = duplicate.actual_filesize;
+        }
+        bytes.truncate(content_end);
+        sauce.add_to_bytes(bytes)?;
+        Ok(Some(warnings))
+    }
+
+    pub fn repair_file<P: AsRef<Path>>(path: P) -> Result<Option<Vec<Warning>>> {
+        let mut file = open_for_update(&path)?;
+        Sauce::repair_open_file(&mut file).with_path(&path)
+    }
+
+    fn repair_open_file(file: &mut File) -> Result<Option<Vec<Warning>>> {
+        let end = file.seek(SeekFrom::End(0))?;
+        let (offset, tail) = read_tail(file, end)?;
+        let (mut sauce, warnings) = match Sauce::from_bytes_tolerant(&tail) {
+            Some(result) => result,
+            None => return Ok(None),
+        };
+        let mut content_end = offset + sauce.actual_filesize;
+        loop {
+            let (offset, tail) = read_tail(file, content_end as u64)?;
+            match Sauce::from_bytes_tolerant(&tail) {
+                Some((duplicate, _)) => content_end = offset + duplicate.actual_filesize,
+                None => break,
             }
         }
-        Ok(Some(sauce))
+        sauce.write_to_file(file, content_end)?;
+        Ok(Some(warnings))
     }
 }
 
@@ -271,10 +461,17 @@ impl std::fmt::Display for Sauce {
             Some(filetype) => writeln!(f, "filetype: {}", filetype)?,
             None => writeln!(f, "filetype: None")?,
         }
-        writeln!(f, "type info 1: {}", self.info_1)?;
-        writeln!(f, "type info 2: {}", self.info_2)?;
-        writeln!(f, "type info 3: {}", self.info_3)?;
-        writeln!(f, "type info 4: {}", self.info_4)?;
+        for (i, (field, value)) in self
+            .info_fields()
+            .iter()
+            .zip(self.info_values().iter())
+            .enumerate()
+        {
+            match field {
+                Some(field) => writeln!(f, "{}: {}", field, value)?,
+                None => writeln!(f, "type info {}: {}", i + 1, value)?,
+            }
+        }
         writeln!(f, "ice colors: {}", self.ice_colors)?;
         match &self.letter_spacing {
             Some(letter_spacing) => writeln!(f, "letter spacing: {}", letter_spacing)?,
@@ -292,3 +489,179 @@ impl std::fmt::Display for Sauce {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_sauce(content: &[u8], comments: Option<&str>) -> Vec<u8> {
+        let mut sauce = Sauce::new();
+        sauce.title = "title".to_string();
+        sauce.datatype = Some(DataType::Character);
+        sauce.filetype = Some(FileType::ANSI);
+        sauce.comments = comments.map(str::to_string);
+        let mut bytes = content.to_vec();
+        sauce.add_to_bytes(&mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn tolerant_read_reports_warnings() {
+        let mut bytes = with_sauce(b"content", None);
+        bytes.remove(7);
+        let sauce_start = bytes.len() - 128;
+        bytes[sauce_start + 94] = 42;
+        bytes[sauce_start + 105] = 3 << 3;
+        let (sauce, warnings) = Sauce::from_bytes_tolerant(&bytes).unwrap();
+        assert_eq!(sauce.title, "title");
+        assert_eq!(sauce.actual_filesize(), 7);
+        assert_eq!(
+            warnings,
+            vec![
+                Warning::InvalidDataType { value: 42 },
+                Warning::InvalidFileType { value: 1 },
+                Warning::InvalidAspectRatio,
+                Warning::MissingEOF,
+            ]
+        );
+        let error = Sauce::from_bytes(&bytes).err().unwrap();
+        assert!(matches!(
+            error,
+            Error::Sauce {
+                kind: SauceError::InvalidDataType,
+                field: Some("datatype"),
+                ..
+            }
+        ));
+        assert_eq!(error.offset(), Some(sauce_start + 94));
+    }
+
+    #[test]
+    fn tolerant_read_finds_miscounted_comments() {
+        let mut bytes = with_sauce(b"content", Some("one line"));
+        let sauce_start = bytes.len() - 128;
+        bytes[sauce_start + 104] = 3;
+        let (sauce, warnings) = Sauce::from_bytes_tolerant(&bytes).unwrap();
+        assert_eq!(sauce.comments.as_deref(), Some("one line"));
+        assert_eq!(
+            warnings,
+            vec![Warning::CommentCountMismatch {
+                recorded: 3,
+                found: 1,
+            }]
+        );
+        bytes[sauce_start + 104] = 0;
+        bytes.drain(8..8 + 5 + 64);
+        let sauce_start = bytes.len() - 128;
+        bytes[sauce_start + 104] = 2;
+        let (_, warnings) = Sauce::from_bytes_tolerant(&bytes).unwrap();
+        assert_eq!(warnings, vec![Warning::CommentsNotFound { lines: 2 }]);
+        assert!(Sauce::from_bytes_tolerant(b"no record").is_none());
+    }
+
+    #[test]
+    fn repair_rewrites_damaged_records() {
+        let mut bytes = with_sauce(b"content", Some("comment"));
+        let sauce_start = bytes.len() - 128;
+        bytes[sauce_start + 95] = 200;
+        bytes[sauce_start + 104] = 4;
+        let warnings = Sauce::repair_bytes(&mut bytes).unwrap().unwrap();
+        assert_eq!(
+            warnings,
+            vec![
+                Warning::InvalidFileType { value: 200 },
+                Warning::CommentCountMismatch {
+                    recorded: 4,
+                    found: 1,
+                },
+            ]
+        );
+        let sauce = Sauce::from_bytes(&bytes).unwrap().unwrap();
+        assert_eq!(sauce.filetype, Some(FileType::ASCII));
+        assert_eq!(sauce.comments.as_deref(), Some("comment"));
+        assert!(sauce.validate().is_empty());
+        let mut plain = b"content".to_vec();
+        assert!(Sauce::repair_bytes(&mut plain).unwrap().is_none());
+        assert_eq!(plain, b"content");
+    }
+
+    #[test]
+    fn repair_drops_duplicate_records() {
+        let mut bytes = with_sauce(b"content", None);
+        let mut second = Sauce::new();
+        second.comments = Some("second".to_string());
+        bytes.append(&mut second.to_bytes().unwrap());
+        let (_, warnings) = Sauce::from_bytes_tolerant(&bytes).unwrap();
+        assert_eq!(warnings, vec![Warning::DuplicateRecord]);
+        assert!(Sauce::from_bytes(&bytes).is_ok());
+        Sauce::repair_bytes(&mut bytes).unwrap().unwrap();
+        let sauce = Sauce::from_bytes(&bytes).unwrap().unwrap();
+        assert_eq!(sauce.actual_filesize(), 7);
+        assert_eq!(sauce.comments.as_deref(), Some("second"));
+        assert!(Sauce::from_bytes_tolerant(&bytes[..7]).is_none());
+    }
+
+    struct CountingReader {
+        inner: std::io::Cursor<Vec<u8>>,
+        read: usize,
+    }
+
+    impl Read for CountingReader {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let read = self.inner.read(buf)?;
+            self.read += read;
+            Ok(read)
+        }
+    }
+
+    impl Seek for CountingReader {
+        fn seek(&mut self, position: SeekFrom) -> std::io::Result<u64> {
+            self.inner.seek(position)
+        }
+    }
+
+    #[test]
+    fn reader_only_reads_the_tail() {
+        let content = vec![b'x'; 1 << 20];
+        let mut reader = CountingReader {
+            inner: std::io::Cursor::new(with_sauce(&content, Some("tail"))),
+            read: 0,
+        };
+        let sauce = Sauce::from_reader(&mut reader).unwrap().unwrap();
+        assert_eq!(sauce.actual_filesize(), content.len());
+        assert_eq!(sauce.comments.as_deref(), Some("tail"));
+        assert_eq!(reader.read as u64, MAX_RECORD_LENGTH);
+        let mut short = std::io::Cursor::new(with_sauce(b"short", None));
+        let sauce = Sauce::from_reader(&mut short).unwrap().unwrap();
+        assert_eq!(sauce.actual_filesize(), 5);
+    }
+
+    #[test]
+    fn file_updates_seek_to_the_tail() {
+        let file = std::env::temp_dir().join(format!("tmode-sauce-{}.ans", std::process::id()));
+        let content = vec![b'x'; MAX_RECORD_LENGTH as usize * 2];
+        std::fs::write(&file, &content).unwrap();
+        assert!(Sauce::from_file(&file).unwrap().is_none());
+        let mut sauce = Sauce::new();
+        sauce.title = "first".to_string();
+        sauce.add_to_file(&file).unwrap();
+        sauce.title = "second".to_string();
+        sauce.add_to_file(&file).unwrap();
+        let read = Sauce::from_file(&file).unwrap().unwrap();
+        assert_eq!(read.title, "second");
+        assert_eq!(read.actual_filesize(), content.len());
+        assert_eq!(read.file, Some(file.display().to_string()));
+        let mut bytes = std::fs::read(&file).unwrap();
+        bytes.append(&mut Sauce::new().to_bytes().unwrap());
+        std::fs::write(&file, &bytes).unwrap();
+        let warnings = Sauce::repair_file(&file).unwrap().unwrap();
+        assert_eq!(warnings, vec![Warning::DuplicateRecord]);
+        assert_eq!(
+            Sauce::from_file(&file).unwrap().unwrap().actual_filesize(),
+            content.len()
+        );
+        Sauce::remove_from_file(&file).unwrap();
+        assert_eq!(std::fs::read(&file).unwrap(), content);
+        std::fs::remove_file(file).unwrap();
+    }
+}
@@ -0,0 +1,378 @@
+use crate::font::Font;
+use crate::sauce::{AsFileType, DataType, FileType, Sauce};
+use crate::string::CP437Char;
+use serde::Serialize;
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "code", rename_all = "snake_case")]
+pub enum Problem {
+    FilesizeMismatch {
+        filesize: usize,
+        actual_filesize: usize,
+    },
+    InvalidDate {
+        year: String,
+        month: String,
+        date: String,
+    },
+    FiletypeMismatch {
+        datatype: Option<DataType>,
+        filetype: Option<FileType>,
+    },
+    UnsupportedFlag {
+        flag: &'static str,
+    },
+    UnknownFont {
+        font_name: String,
+    },
+    FieldTooLong {
+        field: &'static str,
+        length: usize,
+        maximum: usize,
+    },
+    NonCP437 {
+        field: &'static str,
+    },
+    InfoValueTooLarge {
+        field: &'static str,
+        value: usize,
+    },
+    UnusedInfoValue {
+        field: &'static str,
+        value: usize,
+    },
+}
+
+impl std::fmt::Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Problem::FilesizeMismatch {
+                filesize,
+                actual_filesize,
+            } => write!(
+                f,
+                "filesize is {} but the file is {} bytes",
+                filesize, actual_filesize
+            ),
+            Problem::InvalidDate { year, month, date } => {
+                write!(f, "invalid date \"{}{}{}\"", year, month, date)
+            }
+            Problem::FiletypeMismatch { datatype, filetype } => match (datatype, filetype) {
+                (Some(datatype), Some(filetype)) => {
+                    write!(
+                        f,
+                        "filetype {} is invalid for datatype {}",
+                        filetype, datatype
+                    )
+                }
+                (None, Some(filetype)) => write!(f, "filetype {} has no datatype", filetype),
+                (Some(datatype), None) => write!(f, "datatype {} has no filetype", datatype),
+                (None, None) => write!(f, "datatype and filetype are not set"),
+            },
+            Problem::UnsupportedFlag { flag } => {
+                write!(f, "{} is not supported by this filetype", flag)
+            }
+            Problem::UnknownFont { font_name } => write!(f, "unknown font \"{}\"", font_name),
+            Problem::FieldTooLong {
+                field,
+                length,
+                maximum,
+            } => write!(
+                f,
+                "{} is {} characters long (maximum {})",
+                field, length, maximum
+            ),
+            Problem::NonCP437 { field } => write!(f, "{} contains non-CP437 characters", field),
+            Problem::InfoValueTooLarge { field, value } => {
+                write!(f, "{} value {} does not fit in 16 bits", field, value)
+            }
+            Problem::UnusedInfoValue { field, value } => {
+                write!(f, "{} value {} is not used by this filetype", field, value)
+            }
+        }
+    }
+}
+
+fn is_blank(string: &str) -> bool {
+    string.chars().all(|c| c == ' ' || c == '\0')
+}
+
+fn is_leap_year(year: usize) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+fn is_valid_date(year: &str, month: &str, date: &str) -> bool {
+    let parse = |string: &str, length: usize| {
+        if string.len() == length && string.bytes().all(|byte| byte.is_ascii_digit()) {
+            string.parse::<usize>().ok()
+        } else {
+            None
+        }
+    };
+    let (year, month, date) = match (parse(year, 4), parse(month, 2), parse(date, 2)) {
+        (Some(year), Some(month), Some(date)) => (year, month, date),
+        _ => return false,
+    };
+    let days = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        _ => return false,
+    };
+    (1..=days).contains(&date)
+}
+
+pub(crate) fn flags_supported(datatype: Option<DataType>, filetype: Option<FileType>) -> bool {
+    matches!(
+        (datatype, filetype),
+        (Some(DataType::BinaryText), _)
+            | (_, Some(FileType::ASCII))
+            | (_, Some(FileType::ANSI))
+            | (_, Some(FileType::ANSImation))
+    )
+}
+
+impl Sauce {
+    pub fn supports_flags(&self) -> bool {
+        flags_supported(self.datatype, self.filetype)
+    }
+
+    pub fn validate(&self) -> Vec<Problem> {
+        let mut problems = Vec::new();
+        if self.filesize != self.actual_filesize {
+            problems.push(Problem::FilesizeMismatch {
+                filesize: self.filesize,
+                actual_filesize: self.actual_filesize,
+            });
+        }
+        let date = format!("{}{}{}", self.year, self.month, self.date);
+        if !is_blank(&date) && !is_valid_date(&self.year, &self.month, &self.date) {
+            problems.push(Problem::InvalidDate {
+                year: self.year.clone(),
+                month: self.month.clone(),
+                date: self.date.clone(),
+            });
+        }
+        let consistent = match (&self.datatype, &self.filetype) {
+            (Some(DataType::Executable), None) | (None, None) => true,
+            (datatype, Some(filetype)) => {
+                filetype.as_u8().as_filetype(datatype).ok().flatten() == Some(*filetype)
+            }
+            (Some(_), None) => false,
+        };
+        if !consistent {
+            problems.push(Problem::FiletypeMismatch {
+                datatype: self.datatype,
+                filetype: self.filetype,
+            });
+        }
+        if !self.supports_flags() {
+            if self.ice_colors {
+                problems.push(Problem::UnsupportedFlag { flag: "ice colors" });
+            }
+            if self.letter_spacing.is_some() {
+                problems.push(Problem::UnsupportedFlag {
+                    flag: "letter spacing",
+                });
+            }
+            if self.aspect_ratio.is_some() {
+                problems.push(Problem::UnsupportedFlag {
+                    flag: "aspect ratio",
+                });
+            }
+            if !self.font_name.is_empty() {
+                problems.push(Problem::UnsupportedFlag { flag: "font name" });
+            }
+        } else if !self.font_name.is_empty() && self.font_name.parse::<Font>().is_err() {
+            problems.push(Problem::UnknownFont {
+                font_name: self.font_name.clone(),
+            });
+        }
+        let comments = self.comments.clone().unwrap_or_default();
+        let fields = [
+            ("title", &self.title, 35),
+            ("author", &self.author, 20),
+            ("group", &self.group, 20),
+            ("font name", &self.font_name, 22),
+            ("comments", &comments, 255 * 64),
+        ];
+        for (field, value, maximum) in fields.iter() {
+            let length = value.chars().count();
+            if length > *maximum {
+                problems.push(Problem::FieldTooLong {
+                    field,
+                    length,
+                    maximum: *maximum,
+                });
+            }
+            if value.chars().any(|c| c.as_cp437_byte().is_none()) {
+                problems.push(Problem::NonCP437 { field });
+            }
+        }
+        let info = [
+            ("type info 1", self.info_1),
+            ("type info 2", self.info_2),
+            ("type info 3", self.info_3),
+            ("type info 4", self.info_4),
+        ];
+        for ((field, value), info_field) in info.iter().zip(self.info_fields().iter()) {
+            if *value > u16::MAX as usize {
+                problems.push(Problem::InfoValueTooLarge {
+                    field,
+                    value: *value,
+                });
+            }
+            if info_field.is_none() && *value != 0 {
+                problems.push(Problem::UnusedInfoValue {
+                    field,
+                    value: *value,
+                });
+            }
+        }
+        problems
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ansi() -> Sauce {
+        let mut sauce = Sauce::new();
+        sauce.datatype = Some(DataType::Character);
+        sauce.filetype = Some(FileType::ANSI);
+        sauce
+    }
+
+    #[test]
+    fn dates() {
+        assert!(is_valid_date("2024", "02", "29"));
+        assert!(is_valid_date("2000", "02", "29"));
+        assert!(!is_valid_date("1900", "02", "29"));
+        assert!(!is_valid_date("1996", "13", "01"));
+        assert!(!is_valid_date("1996", "04", "31"));
+        assert!(!is_valid_date("96", "04", "01"));
+        let mut sauce = ansi();
+        sauce.year = "1996".to_string();
+        sauce.month = "00".to_string();
+        sauce.date = "10".to_string();
+        assert_eq!(
+            sauce.validate(),
+            vec![Problem::InvalidDate {
+                year: "1996".to_string(),
+                month: "00".to_string(),
+                date: "10".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn written_record_is_clean() {
+        let mut sauce = ansi();
+        sauce.title = "clean".to_string();
+        sauce.year = "1996".to_string();
+        sauce.month = "08".to_string();
+        sauce.date = "14".to_string();
+        sauce.ice_colors = true;
+        sauce.font_name = "IBM VGA".to_string();
+        let mut bytes = b"content".to_vec();
+        sauce.add_to_bytes(&mut bytes).unwrap();
+        let sauce = Sauce::from_bytes(&bytes).unwrap().unwrap();
+        assert_eq!(sauce.validate(), vec![]);
+        let mut truncated = bytes[3..].to_vec();
+        truncated.insert(0, b'c');
+        let sauce = Sauce::from_bytes(&truncated).unwrap().unwrap();
+        assert_eq!(
+            sauce.validate(),
+            vec![Problem::FilesizeMismatch {
+                filesize: 7,
+                actual_filesize: 5,
+            }]
+        );
+    }
+
+    #[test]
+    fn filetype_and_flags() {
+        let mut sauce = Sauce::new();
+        sauce.datatype = Some(DataType::Character);
+        sauce.filetype = Some(FileType::PNG);
+        sauce.ice_colors = true;
+        sauce.font_name = "IBM VGA".to_string();
+        let problems = sauce.validate();
+        assert!(problems.contains(&Problem::FiletypeMismatch {
+            datatype: Some(DataType::Character),
+            filetype: Some(FileType::PNG),
+        }));
+        assert!(problems.contains(&Problem::UnsupportedFlag { flag: "ice colors" }));
+        assert!(problems.contains(&Problem::UnsupportedFlag { flag: "font name" }));
+        let mut sauce = ansi();
+        sauce.font_name = "Nonexistent".to_string();
+        assert_eq!(
+            sauce.validate(),
+            vec![Problem::UnknownFont {
+                font_name: "Nonexistent".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn unused_info_values() {
+        let mut sauce = ansi();
+        sauce.info_1 = 80;
+        sauce.info_2 = 25;
+        sauce.info_3 = 16;
+        assert_eq!(
+            sauce.validate(),
+            vec![Problem::UnusedInfoValue {
+                field: "type info 3",
+                value: 16,
+            }]
+        );
+        assert_eq!(
+            sauce.validate()[0].to_string(),
+            "type info 3 value 16 is not used by this filetype"
+        );
+        let mut sauce = Sauce::new();
+        sauce.datatype = Some(DataType::Audio);
+        sauce.filetype = Some(FileType::WAV);
+        sauce.info_1 = 44100;
+        assert_eq!(
+            sauce.validate(),
+            vec![Problem::UnusedInfoValue {
+                field: "type info 1",
+                value: 44100,
+            }]
+        );
+        sauce.filetype = Some(FileType::SMP16);
+        assert_eq!(sauce.validate(), vec![]);
+    }
+
+    #[test]
+    fn field_lengths_and_encoding() {
+        let mut sauce = ansi();
+        sauce.title = "x".repeat(36);
+        sauce.author = "\u{263a}".to_string();
+        sauce.info_1 = 70000;
+        let problems = sauce.validate();
+        assert_eq!(
+            problems,
+            vec![
+                Problem::FieldTooLong {
+                    field: "title",
+                    length: 36,
+                    maximum: 35,
+                },
+                Problem::InfoValueTooLarge {
+                    field: "type info 1",
+                    value: 70000,
+                },
+            ]
+        );
+        sauce.title.clear();
+        sauce.author = "\u{1f600}".to_string();
+        assert!(sauce
+            .validate()
+            .contains(&Problem::NonCP437 { field: "author" }));
+    }
+}
@@ -0,0 +1,52 @@
+use crate::sauce::SauceError;
+use serde::Serialize;
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "code", rename_all = "snake_case")]
+pub enum Warning {
+    InvalidDataType { value: u8 },
+    InvalidFileType { value: u8 },
+    InvalidLetterSpacing,
+    InvalidAspectRatio,
+    CommentsNotFound { lines: usize },
+    CommentCountMismatch { recorded: usize, found: usize },
+    MissingEOF,
+    DuplicateRecord,
+}
+
+impl std::fmt::Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Warning::InvalidDataType { value } => write!(f, "invalid datatype {}", value),
+            Warning::InvalidFileType { value } => write!(f, "invalid filetype {}", value),
+            Warning::InvalidLetterSpacing => write!(f, "invalid letter spacing value"),
+            Warning::InvalidAspectRatio => write!(f, "invalid aspect ratio value"),
+            Warning::CommentsNotFound { lines } => {
+                write!(f, "{} lines of comments recorded but none found", lines)
+            }
+            Warning::CommentCountMismatch { recorded, found } => write!(
+                f,
+                "{} lines of comments recorded but {} found",
+                recorded, found
+            ),
+            Warning::MissingEOF => write!(f, "EOF value not found"),
+            Warning::DuplicateRecord => write!(f, "another SAUCE record precedes this one"),
+        }
+    }
+}
+
+impl Warning {
+    pub fn as_error(&self) -> Option<SauceError> {
+        match self {
+            Warning::InvalidDataType { .. } => Some(SauceError::InvalidDataType),
+            Warning::InvalidFileType { .. } => Some(SauceError::InvalidFileType),
+            Warning::InvalidLetterSpacing => Some(SauceError::InvalidLetterSpacingValue),
+            Warning::InvalidAspectRatio => Some(SauceError::InvalidAspectRatioValue),
+            Warning::CommentsNotFound { .. } | Warning::CommentCountMismatch { .. } => {
+                Some(SauceError::CommentsNotFound)
+            }
+            Warning::MissingEOF => Some(SauceError::EOFValueNotFound),
+            Warning::DuplicateRecord => None,
+        }
+    }
+}
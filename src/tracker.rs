@@ -0,0 +1,296 @@
+use crate::bytes::AsUSize;
+use crate::error::{Context, Result};
+use crate::sauce::{FileType, Sauce};
+use crate::string::CP437String;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+static COMMENT_WIDTH: usize = 64;
+static HEADER_LENGTH: u64 = 2048;
+
+pub struct Module {
+    pub title: String,
+    pub tracker: String,
+    pub channels: usize,
+    pub patterns: usize,
+    pub instruments: usize,
+    pub samples: usize,
+}
+
+fn text(bytes: &[u8]) -> String {
+    let end = bytes
+        .iter()
+        .position(|byte| *byte == 0)
+        .unwrap_or(bytes.len());
+    String::from_cp437_bytes(&bytes[..end]).trim().to_string()
+}
+
+fn le(bytes: &[u8], offset: usize, length: usize) -> Option<usize> {
+    bytes.get(offset..offset + length).map(AsUSize::as_usize)
+}
+
+fn mod_channels(tag: &[u8]) -> Option<(usize, &'static str)> {
+    match tag {
+        b"M.K." | b"M!K!" => Some((4, "ProTracker")),
+        b"FLT4" => Some((4, "StarTrekker")),
+        b"FLT8" => Some((8, "StarTrekker")),
+        b"CD81" | b"OKTA" => Some((8, "Oktalyzer")),
+        [digit @ b'1'..=b'9', b'C', b'H', b'N'] => Some(((digit - b'0') as usize, "FastTracker")),
+        [tens @ b'1'..=b'9', units @ b'0'..=b'9', b'C', b'H'] => Some((
+            ((tens - b'0') * 10 + (units - b'0')) as usize,
+            "FastTracker",
+        )),
+        _ => None,
+    }
+}
+
+fn from_mod(bytes: &[u8]) -> Option<Module> {
+    let (channels, tracker) = mod_channels(bytes.get(1080..1084)?)?;
+    let orders = bytes.get(952..1080)?;
+    let patterns = *orders.iter().max()? as usize + 1;
+    Some(Module {
+        title: text(bytes.get(0..20)?),
+        tracker: tracker.to_string(),
+        channels,
+        patterns,
+        instruments: 0,
+        samples: 31,
+    })
+}
+
+fn scream_tracker_version(cwt: usize) -> String {
+    let version = format!("{}.{:02x}", (cwt >> 8) & 0xf, cwt & 0xff);
+    match cwt >> 12 {
+        1 => format!("Scream Tracker {}", version),
+        2 => format!("Imago Orpheus {}", version),
+        3 => format!("Impulse Tracker {}", version),
+        4 => "Schism Tracker".to_string(),
+        5 => "OpenMPT".to_string(),
+        _ => String::new(),
+    }
+}
+
+fn from_s3m(bytes: &[u8]) -> Option<Module> {
+    if bytes.get(44..48)? != b"SCRM" {
+        return None;
+    }
+    let channels = bytes
+        .get(64..96)?
+        .iter()
+        .filter(|channel| **channel < 16)
+        .count();
+    Some(Module {
+        title: text(bytes.get(0..28)?),
+        tracker: scream_tracker_version(le(bytes, 40, 2)?),
+        channels,
+        patterns: le(bytes, 36, 2)?,
+        instruments: le(bytes, 34, 2)?,
+        samples: 0,
+    })
+}
+
+fn from_xm(bytes: &[u8]) -> Option<Module> {
+    if bytes.get(0..17)? != b"Extended Module: " {
+        return None;
+    }
+    Some(Module {
+        title: text(bytes.get(17..37)?),
+        tracker: text(bytes.get(38..58)?),
+        channels: le(bytes, 68, 2)?,
+        patterns: le(bytes, 70, 2)?,
+        instruments: le(bytes, 72, 2)?,
+        samples: 0,
+    })
+}
+
+fn from_it(bytes: &[u8]) -> Option<Module> {
+    if bytes.get(0..4)? != b"IMPM" {
+        return None;
+    }
+    let cwt = le(bytes, 0x28, 2)?;
+    let tracker = match cwt >> 12 {
+        0 => format!("Impulse Tracker {}.{:02x}", (cwt >> 8) & 0xf, cwt & 0xff),
+        _ => scream_tracker_version(cwt),
+    };
+    let channels = bytes
+        .get(0x40..0x80)?
+        .iter()
+        .filter(|pan| **pan < 128)
+        .count();
+    Some(Module {
+        title: text(bytes.get(4..30)?),
+        tracker,
+        channels,
+        patterns: le(bytes, 0x26, 2)?,
+        instruments: le(bytes, 0x22, 2)?,
+        samples: le(bytes, 0x24, 2)?,
+    })
+}
+
+impl Module {
+    pub fn from_bytes(bytes: &[u8], filetype: FileType) -> Option<Module> {
+        match filetype {
+            FileType::MOD => from_mod(bytes),
+            FileType::S3M => from_s3m(bytes),
+            FileType::XM => from_xm(bytes),
+            FileType::IT => from_it(bytes),
+            _ => None,
+        }
+    }
+
+    pub fn from_file<P: AsRef<Path>>(file: P, filetype: FileType) -> Result<Option<Module>> {
+        let mut bytes = Vec::new();
+        File::open(&file)
+            .with_path(&file)?
+            .take(HEADER_LENGTH)
+            .read_to_end(&mut bytes)
+            .with_path(&file)?;
+        Ok(Module::from_bytes(&bytes, filetype))
+    }
+
+    pub fn comments(&self) -> String {
+        let mut lines = Vec::new();
+        if !self.tracker.is_empty() {
+            lines.push(format!("Tracker: {}", self.tracker));
+        }
+        let mut counts = vec![
+            format!("Channels: {}", self.channels),
+            format!("Patterns: {}", self.patterns),
+        ];
+        if self.instruments > 0 {
+            counts.push(format!("Instruments: {}", self.instruments));
+        }
+        if self.samples > 0 {
+            counts.push(format!("Samples: {}", self.samples));
+        }
+        lines.push(counts.join(", "));
+        lines
+            .iter()
+            .map(|line| format!("{:width$}", line, width = COMMENT_WIDTH))
+            .collect::<String>()
+            .trim_end()
+            .to_string()
+    }
+
+    pub fn apply_to_sauce(&self, sauce: &mut Sauce, overwrite: bool) {
+        if !self.title.is_empty() && (overwrite || sauce.title.is_empty()) {
+            sauce.title = self.title.clone();
+        }
+        if overwrite || sauce.comments.is_none() {
+            sauce.comments = Some(self.comments());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn put(bytes: &mut [u8], offset: usize, data: &[u8]) {
+        bytes[offset..offset + data.len()].copy_from_slice(data);
+    }
+
+    #[test]
+    fn protracker_mod() {
+        let mut bytes = vec![0; 1084];
+        put(&mut bytes, 0, b"space debris");
+        put(&mut bytes, 952, &[0, 3, 1]);
+        put(&mut bytes, 1080, b"M.K.");
+        let module = Module::from_bytes(&bytes, FileType::MOD).unwrap();
+        assert_eq!(module.title, "space debris");
+        assert_eq!(module.tracker, "ProTracker");
+        assert_eq!(module.channels, 4);
+        assert_eq!(module.patterns, 4);
+        assert_eq!(module.samples, 31);
+        put(&mut bytes, 1080, b"12CH");
+        let module = Module::from_bytes(&bytes, FileType::MOD).unwrap();
+        assert_eq!(
+            (module.channels, module.tracker.as_str()),
+            (12, "FastTracker")
+        );
+        put(&mut bytes, 1080, b"????");
+        assert!(Module::from_bytes(&bytes, FileType::MOD).is_none());
+    }
+
+    #[test]
+    fn scream_tracker_s3m() {
+        let mut bytes = vec![0xff; 96];
+        put(&mut bytes, 0, b"unreal\0");
+        put(&mut bytes, 34, &[5, 0, 9, 0]);
+        put(&mut bytes, 40, &[0x20, 0x13]);
+        put(&mut bytes, 44, b"SCRM");
+        put(&mut bytes, 64, &[0, 1, 8, 9]);
+        let module = Module::from_bytes(&bytes, FileType::S3M).unwrap();
+        assert_eq!(module.title, "unreal");
+        assert_eq!(module.tracker, "Scream Tracker 3.20");
+        assert_eq!(module.channels, 4);
+        assert_eq!(module.patterns, 9);
+        assert_eq!(module.instruments, 5);
+        let file = std::env::temp_dir().join(format!("tmode-tracker-{}.s3m", std::process::id()));
+        std::fs::write(&file, &bytes).unwrap();
+        let module = Module::from_file(&file, FileType::S3M).unwrap().unwrap();
+        assert_eq!(module.title, "unreal");
+        std::fs::remove_file(&file).unwrap();
+        assert!(Module::from_file(&file, FileType::S3M).is_err());
+    }
+
+    #[test]
+    fn fasttracker_xm() {
+        let mut bytes = vec![0; 80];
+        put(&mut bytes, 0, b"Extended Module: ");
+        put(&mut bytes, 17, b"stardust memories");
+        put(&mut bytes, 38, b"FastTracker v2.00");
+        put(&mut bytes, 68, &[16, 0, 40, 0, 12, 0]);
+        let module = Module::from_bytes(&bytes, FileType::XM).unwrap();
+        assert_eq!(module.title, "stardust memories");
+        assert_eq!(module.tracker, "FastTracker v2.00");
+        assert_eq!(module.channels, 16);
+        assert_eq!(module.patterns, 40);
+        assert_eq!(module.instruments, 12);
+    }
+
+    #[test]
+    fn impulse_tracker_it() {
+        let mut bytes = vec![0xa0; 0x80];
+        put(&mut bytes, 0, b"IMPM");
+        put(&mut bytes, 4, b"beyond music\0");
+        put(&mut bytes, 0x22, &[3, 0, 7, 0, 20, 0, 0x14, 0x02]);
+        put(&mut bytes, 0x40, &[32, 32, 0, 64, 100]);
+        let module = Module::from_bytes(&bytes, FileType::IT).unwrap();
+        assert_eq!(module.title, "beyond music");
+        assert_eq!(module.tracker, "Impulse Tracker 2.14");
+        assert_eq!(module.channels, 5);
+        assert_eq!(module.patterns, 20);
+        assert_eq!(module.instruments, 3);
+        assert_eq!(module.samples, 7);
+        assert!(Module::from_bytes(&bytes, FileType::XM).is_none());
+    }
+
+    #[test]
+    fn sauce_title_and_comments() {
+        let module = Module {
+            title: "beyond music".to_string(),
+            tracker: "Impulse Tracker 2.14".to_string(),
+            channels: 5,
+            patterns: 20,
+            instruments: 0,
+            samples: 7,
+        };
+        let comments = module.comments();
+        assert_eq!(
+            comments,
+            format!(
+                "{:64}Channels: 5, Patterns: 20, Samples: 7",
+                "Tracker: Impulse Tracker 2.14"
+            )
+        );
+        let mut sauce = Sauce::new();
+        sauce.title = "kept".to_string();
+        module.apply_to_sauce(&mut sauce, false);
+        assert_eq!(sauce.title, "kept");
+        assert_eq!(sauce.comments, Some(comments));
+        module.apply_to_sauce(&mut sauce, true);
+        assert_eq!(sauce.title, "beyond music");
+    }
+}